@@ -0,0 +1,188 @@
+use bech32::{ToBase32, Variant};
+use cosmwasm_std::{to_vec, Addr, Binary, Deps, Env, StdError, StdResult, Uint128};
+use ripemd::Ripemd160;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::rng::Prng;
+use crate::state::{is_permit_revoked, load_viewing_key_hash};
+
+pub const VIEWING_KEY_PREFIX: &str = "api_key_";
+pub const VIEWING_KEY_ERROR: &str = "Wrong viewing key for this address or viewing key not set";
+
+/// Prefix of the accounts permits are signed by
+const ACCOUNT_HRP: &str = "secret";
+
+/// New viewing key for `account`, derived from the block's randomness and the entropy they sent
+pub fn new_viewing_key(env: &Env, account: &Addr, entropy: &str) -> String {
+    let mut seed = env.block.random.clone().unwrap_or_default().to_vec();
+    seed.extend_from_slice(&env.block.height.to_be_bytes());
+    seed.extend_from_slice(account.as_bytes());
+    seed.extend_from_slice(entropy.as_bytes());
+
+    let key = Prng::new(&seed).derive("viewing_key").rand_bytes();
+
+    format!("{}{}", VIEWING_KEY_PREFIX, Binary::from(key.as_slice()).to_base64())
+}
+
+/// Only the hash of a viewing key is stored
+pub fn hash_viewing_key(key: &str) -> [u8; 32] {
+    Sha256::digest(key.as_bytes()).into()
+}
+
+/// Errors unless `key` is the viewing key `account` has set
+pub fn check_viewing_key(deps: Deps, account: &Addr, key: &str) -> StdResult<()> {
+    let hash = hash_viewing_key(key);
+
+    match load_viewing_key_hash(deps.storage, account)? {
+        // compare every byte so the time taken doesn't give away how much of the key matched
+        Some(stored) if stored.iter().zip(hash).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0 => Ok(()),
+        _ => Err(StdError::generic_err(VIEWING_KEY_ERROR)),
+    }
+}
+
+/// What a permit lets its holder query
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    History,
+    PendingSpins,
+    Balance,
+    /// Everything the account could query with its viewing key
+    Owner,
+}
+
+/// SNIP-24 query permit, signed offline by the account it gives access to
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Permit {
+    pub params: PermitParams,
+    pub signature: PermitSignature,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PermitParams {
+    /// Contracts the permit can be used with
+    pub allowed_tokens: Vec<String>,
+    pub permit_name: String,
+    pub chain_id: String,
+    pub permissions: Vec<Permission>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PermitSignature {
+    pub pub_key: PubKey,
+    pub signature: Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PubKey {
+    /// Always "tendermint/PubKeySecp256k1"
+    pub r#type: String,
+    /// Compressed secp256k1 key
+    pub value: Binary,
+}
+
+/// The amino sign doc wallets sign a permit as. Fields are in the order amino sorts them
+#[derive(Serialize)]
+struct SignDoc<'a> {
+    account_number: Uint128,
+    chain_id: &'a str,
+    fee: Fee,
+    memo: &'a str,
+    msgs: [PermitMsg<'a>; 1],
+    sequence: Uint128,
+}
+
+#[derive(Serialize)]
+struct Fee {
+    amount: [FeeAmount; 1],
+    gas: Uint128,
+}
+
+#[derive(Serialize)]
+struct FeeAmount {
+    amount: Uint128,
+    denom: &'static str,
+}
+
+#[derive(Serialize)]
+struct PermitMsg<'a> {
+    r#type: &'static str,
+    value: PermitContent<'a>,
+}
+
+#[derive(Serialize)]
+struct PermitContent<'a> {
+    allowed_tokens: &'a [String],
+    permissions: &'a [Permission],
+    permit_name: &'a str,
+}
+
+/// Bytes a wallet signs for these permit params
+pub fn permit_sign_bytes(params: &PermitParams) -> StdResult<Vec<u8>> {
+    to_vec(&SignDoc {
+        account_number: Uint128::zero(),
+        chain_id: &params.chain_id,
+        fee: Fee {
+            amount: [FeeAmount { amount: Uint128::zero(), denom: "uscrt" }],
+            gas: Uint128::one(),
+        },
+        memo: "",
+        msgs: [PermitMsg {
+            r#type: "query_permit",
+            value: PermitContent {
+                allowed_tokens: &params.allowed_tokens,
+                permissions: &params.permissions,
+                permit_name: &params.permit_name,
+            },
+        }],
+        sequence: Uint128::zero(),
+    })
+}
+
+/// Account address of a compressed secp256k1 public key
+pub fn pubkey_to_account(pubkey: &[u8]) -> StdResult<Addr> {
+    let hash = Ripemd160::digest(Sha256::digest(pubkey));
+
+    bech32::encode(ACCOUNT_HRP, hash.to_base32(), Variant::Bech32)
+        .map(Addr::unchecked)
+        .map_err(|err| StdError::generic_err(format!("Can't encode account address: {}", err)))
+}
+
+/// Checks a permit was signed for this contract on this chain, hasn't been revoked and grants
+/// `permission`. Returns the account that signed it
+pub fn validate_permit(deps: Deps, env: &Env, permit: &Permit, permission: Permission) -> StdResult<Addr> {
+    let params = &permit.params;
+
+    // the signature covers the chain id, but a permit signed for a testnet would verify just as well
+    if params.chain_id != env.block.chain_id {
+        return Err(StdError::generic_err(format!("Permit was signed for chain {}", params.chain_id)));
+    }
+
+    if !params.allowed_tokens.contains(&env.contract.address.to_string()) {
+        return Err(StdError::generic_err("Permit doesn't apply to this contract"));
+    }
+
+    if !params.permissions.iter().any(|p| *p == permission || *p == Permission::Owner) {
+        return Err(StdError::generic_err(format!("Permit doesn't grant the {:?} permission", permission)));
+    }
+
+    let pubkey = permit.signature.pub_key.value.as_slice();
+    let hash = Sha256::digest(permit_sign_bytes(params)?);
+
+    let valid = deps.api.secp256k1_verify(&hash, permit.signature.signature.as_slice(), pubkey)
+        .map_err(|err| StdError::generic_err(format!("Invalid permit signature: {}", err)))?;
+
+    if !valid {
+        return Err(StdError::generic_err("Invalid permit signature"));
+    }
+
+    let account = pubkey_to_account(pubkey)?;
+
+    if is_permit_revoked(deps.storage, &account, &params.permit_name)? {
+        return Err(StdError::generic_err(format!("Permit {} was revoked", params.permit_name)));
+    }
+
+    Ok(account)
+}
@@ -1,12 +1,18 @@
 use std::collections::HashMap;
-use cosmwasm_std::{entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Coin, Uint128, StdError, BankMsg, Event, CosmosMsg};
-use rand_core::RngCore;
-
-
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use cosmwasm_std::{entry_point, to_binary, to_vec, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Coin, Uint128, Storage, BankMsg, Event, CosmosMsg, ContractResult, Decimal, Empty, QueryRequest, SystemError, SystemResult, WasmQuery};
+use sha2::{Digest, Sha256};
+
+use crate::auth::{check_viewing_key, hash_viewing_key, new_viewing_key, Permission, Permit, validate_permit};
+use crate::error::ContractError;
+use crate::migrations::{CONTRACT_NAME, CONTRACT_VERSION, migrate_state, UNVERSIONED, Version};
+use crate::msg::{ConfigUpdate, ExecuteAnswer, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, QueryWithPermit, StatusResponse};
+use crate::queries::{query_balance, query_config, query_history, query_pending_admin, query_pending_spins, query_roles, query_round, query_stats};
 use crate::rng::Prng;
-use crate::state::{Config, load_admin, load_config, save_admin, save_config};
-use crate::types::{Bet, CornerType, GameResult, LineType};
+use crate::state::{add_coins, Config, ContractVersion, credit_balance, debit_balance, grant_role, has_role, load_admin, load_balance, load_config, load_contract_version, imprison_bets, load_imprisoned_bets, load_latest_round, load_pending_admin, load_pending_spin, load_reserved_amount, load_round_entries, load_round_entry, load_round_liabilities, load_status, next_roll_nonce, next_spin_id, open_round, PendingAdmin, PendingSpin, push_spin_record, record_loss_window, record_roll, record_wagers, release_funds, remove_pending_admin, remove_pending_spin, remove_round_liabilities, reserve_funds, revoke_permit, revoke_role, Role, RoundEntry, RoundStatus, save_admin, save_balance, save_config, save_contract_version, save_pending_admin, save_pending_spin, save_round, save_round_entry, save_round_liabilities, save_status, save_viewing_key_hash, SpinRecord, take_imprisoned_bets};
+use crate::types::{AnnouncedBet, Bet, CircuitBreaker, ContractCallers, ContractStatus, CornerType, GameResult, LineType, PayoutMode, pocket_label, SpinMode, WheelVariant, ZeroRule};
+
+/// Blocks a proposed admin has to accept in, unless the proposal says otherwise. About a week
+const DEFAULT_ADMIN_PROPOSAL_EXPIRY: u64 = 100_000;
 
 #[entry_point]
 pub fn instantiate(
@@ -14,15 +20,27 @@ pub fn instantiate(
     _env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
 
-    // save init params to state
-    save_config(deps.storage, &crate::state::Config {
-        min_bet: msg.min_bet.unwrap_or(0),
-        max_bet: msg.max_bet.unwrap_or(u64::MAX),
-        max_total: msg.max_total.unwrap_or(u64::MAX),
+    let config = Config {
+        min_bet: msg.min_bet.unwrap_or_default(),
+        max_bet: msg.max_bet.unwrap_or(Uint128::MAX),
+        max_total: msg.max_total.unwrap_or(Uint128::MAX),
         supported_denoms: msg.supported_denoms.unwrap_or(vec!["uscrt".to_string()]),
-    })?;
+        denom_limits: msg.denom_limits.unwrap_or_default(),
+        wheel: msg.wheel.unwrap_or_default(),
+        payouts: msg.payouts.unwrap_or_default(),
+        zero_rule: msg.zero_rule.unwrap_or_default(),
+        spin_mode: msg.spin_mode.unwrap_or_default(),
+        circuit_breaker: msg.circuit_breaker,
+        contract_callers: msg.contract_callers.unwrap_or_default(),
+        payout_mode: msg.payout_mode.unwrap_or_default(),
+    };
+    config.validate()?;
+
+    // save init params to state
+    save_config(deps.storage, &config)?;
+    save_contract_version(deps.storage, &ContractVersion { contract: CONTRACT_NAME.to_string(), version: CONTRACT_VERSION.to_string() })?;
 
     if let Some(admin) = msg.admin {
         save_admin(deps.storage, &admin)?
@@ -39,16 +57,23 @@ pub fn execute(
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
-) -> Result<Response, StdError> {
+) -> Result<Response, ContractError> {
+
+    check_status(deps.storage, &msg)?;
 
     match msg {
-        ExecuteMsg::Bet { bets } =>
-            handle_game_result(deps, env, info, bets),
+        ExecuteMsg::Bet { bets, announced_bets, client_seed } =>
+            handle_game_result(deps, env, info, bets, announced_bets.unwrap_or_default(), client_seed),
         ExecuteMsg::AdminWithdraw { coin } => {
-            let admin = load_admin(deps.storage)?;
+            require_role(deps.as_ref(), &info.sender, Role::Treasurer)?;
+
+            // funds set aside for pending spins belong to the players
+            let reserved = load_reserved_amount(deps.storage, &coin.denom)?;
+            let balance = deps.querier.query_balance(&env.contract.address, &coin.denom)?;
 
-            if admin != info.sender {
-                return Err(StdError::generic_err("You no take candle"));
+            let available = balance.amount.saturating_sub(reserved);
+            if available < coin.amount {
+                return Err(ContractError::FundsReserved { available: Coin { denom: coin.denom, amount: available } });
             }
 
             let msg = BankMsg::Send { to_address: info.sender.to_string(), amount: vec![coin] };
@@ -57,20 +82,237 @@ pub fn execute(
                 .add_message(msg)
             )
         }
-        ExecuteMsg::ChangeAdmin { admin } => {
-            let prev_admin = load_admin(deps.storage)?;
+        ExecuteMsg::ProposeAdmin { admin, expiry_blocks } => {
+            require_role(deps.as_ref(), &info.sender, Role::Owner)?;
+
+            let address = deps.api.addr_validate(&admin)?;
+            let expires_at = env.block.height.saturating_add(expiry_blocks.unwrap_or(DEFAULT_ADMIN_PROPOSAL_EXPIRY));
+            save_pending_admin(deps.storage, &PendingAdmin { address, expires_at })?;
+
+            Ok(Response::new().add_attribute_plaintext("expires_at", expires_at.to_string()))
+        }
+        ExecuteMsg::AcceptAdmin {} => {
+            let pending = match load_pending_admin(deps.storage)? {
+                Some(pending) if pending.address == info.sender => pending,
+                _ => return Err(ContractError::Unauthorized),
+            };
+
+            if env.block.height > pending.expires_at {
+                return Err(ContractError::ProposalExpired { expired_at: pending.expires_at });
+            }
+
+            save_admin(deps.storage, &pending.address)?;
+            remove_pending_admin(deps.storage);
+
+            Ok(Response::default())
+        }
+        ExecuteMsg::CancelAdminProposal {} => {
+            require_role(deps.as_ref(), &info.sender, Role::Owner)?;
+
+            remove_pending_admin(deps.storage);
+
+            Ok(Response::default())
+        }
+        ExecuteMsg::SetPayouts { payouts } => {
+            // the payouts set the house edge
+            require_role(deps.as_ref(), &info.sender, Role::Owner)?;
+
+            let mut config = load_config(deps.storage)?;
+            config.payouts = payouts;
+            config.validate()?;
+            save_config(deps.storage, &config)?;
+
+            Ok(Response::default())
+        }
+        ExecuteMsg::UpdateConfig(update) => {
+            require_role(deps.as_ref(), &info.sender, update_role(&update))?;
+
+            let mut config = load_config(deps.storage)?;
+            update_config(&mut config, update)?;
+            save_config(deps.storage, &config)?;
+
+            Ok(Response::default())
+        }
+        ExecuteMsg::PlaceBets { bets, announced_bets } =>
+            handle_place_bets(deps, env, info, bets, announced_bets.unwrap_or_default()),
+        ExecuteMsg::Settle { spin_id } =>
+            handle_settle(deps, env, spin_id),
+        ExecuteMsg::OpenRound {} =>
+            handle_open_round(deps, env),
+        ExecuteMsg::PlaceRoundBets { bets, announced_bets } =>
+            handle_place_round_bets(deps, env, info, bets, announced_bets.unwrap_or_default()),
+        ExecuteMsg::CloseRound {} =>
+            handle_close_round(deps, env, info),
+        ExecuteMsg::SettleRound { limit } =>
+            handle_settle_round(deps, env, limit),
+        ExecuteMsg::SetStatus { status } => {
+            // pausers can halt betting on a running table, anything else is up to the owner. A
+            // pause can't lift a full stop
+            let current = load_status(deps.storage)?;
+            let role = match (current, status) {
+                (ContractStatus::Running, ContractStatus::BettingPaused) => Role::Pauser,
+                _ => Role::Owner,
+            };
+            require_role(deps.as_ref(), &info.sender, role)?;
+
+            save_status(deps.storage, status)?;
+
+            Ok(Response::default())
+        }
+        ExecuteMsg::GrantRole { role, address } => {
+            require_role(deps.as_ref(), &info.sender, Role::Owner)?;
+
+            if role == Role::Owner {
+                return Err(ContractError::OwnerNotGrantable);
+            }
+
+            let address = deps.api.addr_validate(&address)?;
+            grant_role(deps.storage, role, &address)?;
 
-            if prev_admin != info.sender {
-                return Err(StdError::generic_err("You no take candle"));
+            Ok(Response::default())
+        }
+        ExecuteMsg::RevokeRole { role, address } => {
+            require_role(deps.as_ref(), &info.sender, Role::Owner)?;
+
+            if role == Role::Owner {
+                return Err(ContractError::OwnerNotGrantable);
             }
 
-            save_admin(deps.storage, &admin)?;
+            let address = deps.api.addr_validate(&address)?;
+            revoke_role(deps.storage, role, &address)?;
+
+            Ok(Response::default())
+        }
+        ExecuteMsg::CreateViewingKey { entropy } => {
+            let key = new_viewing_key(&env, &info.sender, &entropy);
+            save_viewing_key_hash(deps.storage, &info.sender, &hash_viewing_key(&key))?;
+
+            Ok(Response::new().set_data(to_binary(&ExecuteAnswer::CreateViewingKey { key })?))
+        }
+        ExecuteMsg::SetViewingKey { key } => {
+            save_viewing_key_hash(deps.storage, &info.sender, &hash_viewing_key(&key))?;
+
+            Ok(Response::default())
+        }
+        ExecuteMsg::Claim { denoms } =>
+            handle_claim(deps, info, denoms),
+        ExecuteMsg::RevokePermit { permit_name } => {
+            revoke_permit(deps.storage, &info.sender, &permit_name)?;
 
             Ok(Response::default())
         }
     }
 }
 
+#[entry_point]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let from = match load_contract_version(deps.storage)? {
+        Some(stored) if stored.contract != CONTRACT_NAME =>
+            return Err(ContractError::WrongContract { contract: stored.contract }),
+        Some(stored) => stored.version.parse::<Version>()?,
+        None => UNVERSIONED,
+    };
+    let to = CONTRACT_VERSION.parse::<Version>()?;
+
+    if from > to {
+        return Err(ContractError::Downgrade { from: from.to_string(), to: to.to_string() });
+    }
+
+    migrate_state(deps.storage, from)?;
+
+    if let Some(update) = msg.config {
+        let mut config = load_config(deps.storage)?;
+        update_config(&mut config, update)?;
+        save_config(deps.storage, &config)?;
+    }
+
+    save_contract_version(deps.storage, &ContractVersion { contract: CONTRACT_NAME.to_string(), version: CONTRACT_VERSION.to_string() })?;
+
+    Ok(Response::new()
+        .add_attribute_plaintext("previous_version", from.to_string())
+        .add_attribute_plaintext("version", CONTRACT_VERSION)
+    )
+}
+
+/// Role needed to apply `update`. Operators look after the limits and denoms, the table's
+/// safeguards and how it pays out are up to the owner
+fn update_role(update: &ConfigUpdate) -> Role {
+    let ConfigUpdate {
+        min_bet: _,
+        max_bet: _,
+        max_total: _,
+        supported_denoms: _,
+        denom_limits: _,
+        circuit_breaker,
+        remove_circuit_breaker,
+        contract_callers,
+        payout_mode,
+    } = update;
+
+    if circuit_breaker.is_some() || remove_circuit_breaker.is_some() || contract_callers.is_some() || payout_mode.is_some() {
+        Role::Owner
+    } else {
+        Role::Operator
+    }
+}
+
+/// Applies the fields set in `update` and checks the result still makes sense
+fn update_config(config: &mut Config, update: ConfigUpdate) -> Result<(), ContractError> {
+    if let Some(min_bet) = update.min_bet {
+        config.min_bet = min_bet;
+    }
+    if let Some(max_bet) = update.max_bet {
+        config.max_bet = max_bet;
+    }
+    if let Some(max_total) = update.max_total {
+        config.max_total = max_total;
+    }
+    if let Some(supported_denoms) = update.supported_denoms {
+        config.supported_denoms = supported_denoms;
+    }
+    if let Some(denom_limits) = update.denom_limits {
+        config.denom_limits = denom_limits;
+    }
+    if let Some(circuit_breaker) = update.circuit_breaker {
+        config.circuit_breaker = Some(circuit_breaker);
+    }
+    if update.remove_circuit_breaker.unwrap_or_default() {
+        config.circuit_breaker = None;
+    }
+    if let Some(contract_callers) = update.contract_callers {
+        config.contract_callers = contract_callers;
+    }
+    if let Some(payout_mode) = update.payout_mode {
+        config.payout_mode = payout_mode;
+    }
+
+    config.validate()
+}
+
+/// Errors if the table's status doesn't allow the message. Placing bets stops once betting is
+/// paused, settling them once the table is fully stopped
+fn check_status(storage: &dyn Storage, msg: &ExecuteMsg) -> Result<(), ContractError> {
+    let status = load_status(storage)?;
+
+    match msg {
+        ExecuteMsg::Bet { .. } | ExecuteMsg::PlaceBets { .. } | ExecuteMsg::OpenRound {} | ExecuteMsg::PlaceRoundBets { .. }
+            if status != ContractStatus::Running =>
+            Err(ContractError::BettingPaused),
+        ExecuteMsg::Settle { .. } | ExecuteMsg::CloseRound {} | ExecuteMsg::SettleRound { .. }
+            if status == ContractStatus::FullyStopped =>
+            Err(ContractError::TableStopped),
+        _ => Ok(()),
+    }
+}
+
+/// Errors unless `sender` holds `role`. The owner holds every role
+fn require_role(deps: Deps, sender: &Addr, role: Role) -> Result<(), ContractError> {
+    if load_admin(deps.storage)? == *sender || (role != Role::Owner && has_role(deps.storage, role, sender)?) {
+        return Ok(());
+    }
+
+    Err(ContractError::Unauthorized)
+}
 
 fn corner_result(winner: u32, corner: CornerType) -> GameResult {
     match corner {
@@ -90,10 +332,18 @@ fn line_result(winner: u32, line: LineType) -> GameResult {
     }
 }
 
-fn return_winning_numbers(result: u32) -> Vec<GameResult> {
+fn street_result(first: u32) -> GameResult {
+    GameResult::Street { nums: (first, first + 1, first + 2) }
+}
+
+fn six_line_result(first: u32) -> GameResult {
+    GameResult::SixLine { nums: (first, first + 1, first + 2, first + 3, first + 4, first + 5) }
+}
+
+fn return_winning_numbers(result: u32, wheel: WheelVariant) -> Result<Vec<GameResult>, ContractError> {
 
-    if result > 36 {
-        panic!("Can never happen")
+    if result >= wheel.pockets() {
+        return Err(ContractError::PocketOffWheel { pocket: result });
     }
 
     let mut winners: Vec<GameResult> = vec![];
@@ -103,15 +353,47 @@ fn return_winning_numbers(result: u32) -> Vec<GameResult> {
     // exact number
     winners.push(GameResult::Exact {num: result});
 
-    if result == 0 {
-        return winners;
+    // 0, 00, 1, 2, 3
+    if wheel == WheelVariant::DoubleZero && (wheel.is_zero(result) || result <= 3) {
+        winners.push(GameResult::TopLine);
+    }
+
+    // splits, trios and the basket around the zero pockets
+    for (a, b) in wheel.zero_splits() {
+        if result == a || result == b {
+            winners.push(GameResult::Line { nums: (a, b) });
+        }
+    }
+
+    for (a, b, c) in wheel.trios() {
+        if [a, b, c].contains(&result) {
+            winners.push(GameResult::Trio { nums: (a, b, c) });
+        }
+    }
+
+    if wheel == WheelVariant::SingleZero && result <= 3 {
+        winners.push(GameResult::Basket);
+    }
+
+    if wheel.is_zero(result) {
+        return Ok(winners);
+    }
+
+    // streets and six lines
+    let street = result - (result - 1) % 3;
+    winners.push(street_result(street));
+    if street > 1 {
+        winners.push(six_line_result(street - 3));
+    }
+    if street < 34 {
+        winners.push(six_line_result(street));
     }
 
     // odd or even
-    if result % 2 == 0 {
-        winners.push(GameResult::Even);
-    } else {
+    if result % 2 == 1 {
         winners.push(GameResult::Odd);
+    } else {
+        winners.push(GameResult::Even);
     }
 
     // upper or lower
@@ -146,7 +428,7 @@ fn return_winning_numbers(result: u32) -> Vec<GameResult> {
             }
             winners.push(line_result(result, LineType::Left));
         }
-        2 => {
+        _ => {
             winners.push(GameResult::Range2to1Second);
 
             if result != 2 {
@@ -163,17 +445,15 @@ fn return_winning_numbers(result: u32) -> Vec<GameResult> {
             winners.push(line_result(result, LineType::Left));
 
         }
-
-        _ => {panic!("Not possible")}
     }
 
     // under/over lines
     match result {
-        1 | 2 | 3 => {
+        1..=3 => {
             // line under
             winners.push(line_result(result, LineType::Under));
         },
-        34 | 35 | 36 => {
+        34..=36 => {
             // both
             winners.push(line_result(result, LineType::Over));
         },
@@ -188,8 +468,7 @@ fn return_winning_numbers(result: u32) -> Vec<GameResult> {
     match result {
         1..=12 => winners.push(GameResult::Range1to12),
         13..=24 => winners.push(GameResult::Range13to24),
-        25..=36 => winners.push(GameResult::Range25to36),
-        _ => unreachable!(),
+        _ => winners.push(GameResult::Range25to36),
     };
 
     // red or black - american table
@@ -202,268 +481,2279 @@ fn return_winning_numbers(result: u32) -> Vec<GameResult> {
         }
     }
 
-    winners
+    Ok(winners)
 }
 
-fn calculate_sum_coins_of_bets(bets: &Vec<Bet>, config: &Config) -> StdResult<HashMap<String, Uint128>> {
+fn calculate_sum_coins_of_bets(bets: &Vec<Bet>, config: &Config) -> Result<HashMap<String, Uint128>, ContractError> {
     let mut coins: HashMap<String, Uint128> = HashMap::default();
     for b in bets {
 
-        let bet_amount = b.amount.amount.u128() as u64;
-        if !config.supported_denoms.contains(&b.amount.denom) {
-            return Err(StdError::generic_err("Denom unsupported supported"));
+        let bet_amount = b.amount.amount;
+        let denom = &b.amount.denom;
+        if !config.supported_denoms.contains(denom) {
+            return Err(ContractError::UnsupportedDenom { denom: denom.clone() });
+        }
+
+        let (min_bet, max_bet) = config.bet_limits(denom, b.result.category());
+
+        if bet_amount > max_bet {
+            return Err(ContractError::BetAboveMax { denom: denom.clone(), amount: bet_amount, max: max_bet });
+        }
+
+        if bet_amount < min_bet {
+            return Err(ContractError::BetBelowMin { denom: denom.clone(), amount: bet_amount, min: min_bet });
+        }
+
+        if let Some(item) = coins.get_mut(denom) {
+            *item = item.checked_add(bet_amount)?;
+        } else {
+            coins.insert(b.amount.denom.clone(), b.amount.amount);
+        }
+    }
+
+    Ok(coins)
+}
+
+/// Matches the funds sent with a slip against its per denom totals. Funds can be sent at most once
+/// per denom and for no more than its total, nothing else can be sent, and each total has to be
+/// within the table's limits for a whole slip. Whatever the sent funds don't cover is drawn from
+/// the player's `balance`, the coins drawn are returned
+fn reconcile_funds(totals: &HashMap<String, Uint128>, sent_funds: &[Coin], balance: &[Coin], config: &Config) -> Result<Vec<Coin>, ContractError> {
+    for (i, coin) in sent_funds.iter().enumerate() {
+        if sent_funds[..i].iter().any(|c| c.denom == coin.denom) {
+            return Err(ContractError::DuplicateFunds { denom: coin.denom.clone() });
+        }
+
+        if coin.amount.is_zero() {
+            return Err(ContractError::ZeroFunds { denom: coin.denom.clone() });
+        }
+
+        if !totals.contains_key(&coin.denom) {
+            return Err(ContractError::FundsMismatch { denom: coin.denom.clone(), expected: Uint128::zero(), sent: coin.amount });
         }
+    }
+
+    // sorted so the same slip always reports the same denom
+    let mut totals: Vec<(&String, &Uint128)> = totals.iter().collect();
+    totals.sort();
+
+    let mut drawn = vec![];
+
+    for (denom, total) in totals {
+        let sent = sent_funds.iter().find(|c| c.denom == *denom).map(|c| c.amount).unwrap_or_default();
+        if sent > *total {
+            return Err(ContractError::FundsMismatch { denom: denom.clone(), expected: *total, sent });
+        }
+
+        let missing = *total - sent;
+        if !missing.is_zero() {
+            let held = balance.iter().find(|c| c.denom == *denom).map(|c| c.amount).unwrap_or_default();
+            if held < missing {
+                return Err(ContractError::BalanceTooLow { denom: denom.clone(), expected: *total, sent, balance: held });
+            }
+
+            drawn.push(Coin { denom: denom.clone(), amount: missing });
+        }
+
+        let max_total = config.max_total(denom);
+        if *total > max_total {
+            return Err(ContractError::StakeAboveMax { denom: denom.clone(), amount: *total, max: max_total });
+        }
+
+        let min_bet = config.min_bet(denom);
+        if *total < min_bet {
+            return Err(ContractError::BetBelowMin { denom: denom.clone(), amount: *total, min: min_bet });
+        }
+    }
+
+    Ok(drawn)
+}
+
+/// Turns away contracts the table doesn't let bet. Only a definite "no such contract" from the
+/// chain lets a sender through as an account, if the lookup fails for any other reason the bet is
+/// refused rather than risk letting a contract in
+fn check_contract_caller(deps: Deps, sender: &Addr, config: &Config) -> Result<(), ContractError> {
+    let ContractCallers::AllowListed { contracts } = &config.contract_callers else {
+        return Ok(());
+    };
+
+    if contracts.contains(sender) {
+        return Ok(());
+    }
+
+    let query: QueryRequest<Empty> = WasmQuery::ContractInfo { contract_addr: sender.to_string() }.into();
+
+    match deps.querier.raw_query(&to_vec(&query)?) {
+        SystemResult::Err(SystemError::NoSuchContract { .. }) => Ok(()),
+        SystemResult::Ok(ContractResult::Ok(_)) => Err(ContractError::Unauthorized),
+        _ => Err(ContractError::CallerUnverified { address: sender.to_string() }),
+    }
+}
+
+/// Checks a bet slip against the table's rules and the funds sent with it. The part of the slip
+/// paid from the player's balance is debited from it and no longer reserved, the stake now backs
+/// the spin like sent funds would. Returns every coin staked, sent or drawn from the balance
+fn validate_bet_slip(
+    deps: DepsMut,
+    info: &MessageInfo,
+    config: &Config,
+    bets: &[Bet],
+    announced_bets: &[AnnouncedBet],
+) -> Result<Vec<Coin>, ContractError> {
+    check_contract_caller(deps.as_ref(), &info.sender, config)?;
+
+    let wheel = config.wheel;
+
+    // limits and validation apply to the individual bets an announced bet is made of
+    let mut all_bets = bets.to_vec();
+    for announced in announced_bets {
+        match announced.expand(wheel) {
+            Ok(expanded) => all_bets.extend(expanded),
+            Err(reason) => {
+                return Err(ContractError::InvalidBet { bet: String::from(announced.call.clone()), reason });
+            }
+        }
+    }
+
+    // a slip has to stake something, or it would be a free spin that still counts in the stats and
+    // resolves held bets
+    if all_bets.is_empty() {
+        return Err(ContractError::EmptySlip);
+    }
+
+    if let Some(b) = all_bets.iter().find(|b| b.amount.amount.is_zero()) {
+        return Err(ContractError::ZeroBet { bet: String::from(b.result.clone()) });
+    }
+
+    let sums = calculate_sum_coins_of_bets(&all_bets, config)?;
+
+    let balance = load_balance(deps.storage, &info.sender)?;
+    let drawn = reconcile_funds(&sums, &info.funds, &balance, config)?;
+
+    for b in &all_bets {
+        if let Err(reason) = b.result.validate(wheel) {
+            return Err(ContractError::InvalidBet { bet: String::from(b.result.clone()), reason });
+        }
+    }
+
+    if !drawn.is_empty() {
+        debit_balance(deps.storage, &info.sender, &drawn)?;
+        release_funds(deps.storage, &drawn)?;
+    }
+
+    let mut staked = info.funds.clone();
+    add_coins(&mut staked, &drawn)?;
+
+    Ok(staked)
+}
+
+/// Domain of the roll seeds, so they can't collide with seeds hashed for anything else
+const ROLL_SEED_DOMAIN: &[u8] = b"secret-roulette/roll";
+
+/// Longest client seed a player can send with their bets
+const MAX_CLIENT_SEED_LEN: usize = 256;
+
+/// Seed for a single roll. The block's randomness is mixed with the contract, the player, a nonce
+/// that grows with every roll, the transaction's position in the block and the player's own seed,
+/// so every spin gets its own result while still being reproducible from on-chain data
+fn roll_seed(storage: &mut dyn Storage, env: &Env, player: &Addr, client_seed: Option<&str>) -> Result<Vec<u8>, ContractError> {
+    let random = env.block.random.as_ref().ok_or(ContractError::RandomnessUnavailable)?;
+
+    let nonce = next_roll_nonce(storage)?;
+    let tx_index = env.transaction.as_ref().map(|tx| tx.index).unwrap_or_default();
+
+    let mut seed = ROLL_SEED_DOMAIN.to_vec();
+
+    // every part is length prefixed so different inputs can't produce the same seed
+    for part in [
+        random.as_slice(),
+        env.contract.address.as_bytes(),
+        player.as_bytes(),
+        &nonce.to_be_bytes(),
+        &tx_index.to_be_bytes(),
+    ].into_iter().chain(client_seed.map(str::as_bytes)) {
+        seed.extend_from_slice(&(part.len() as u32).to_be_bytes());
+        seed.extend_from_slice(part);
+    }
+
+    Ok(seed)
+}
+
+/// Domain of the seed commitments. The prng keys itself with the plain sha256 of the seed, so
+/// the commitment is kept apart from its key
+const SEED_COMMITMENT_DOMAIN: &[u8] = b"secret-roulette/commit";
 
-        if bet_amount > config.max_bet {
-            return Err(StdError::generic_err("Bet is higher than table maximum"));
+/// sha256 of the domain and the seed, base64 encoded
+fn seed_commitment(seed: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(SEED_COMMITMENT_DOMAIN);
+    hasher.update(seed);
+
+    Binary::from(hasher.finalize().as_slice()).to_base64()
+}
+
+/// A rolled pocket and a commitment to the exact seed it came from
+struct Roll {
+    result: u32,
+    /// see `seed_commitment`
+    seed_commitment: String,
+    /// Revealed once the roll has been settled, so anyone can check it against the commitment and
+    /// replay the prng to get the same pocket
+    seed: Binary,
+}
+
+/// Rolls the wheel and counts the result in the table's stats
+fn roll(storage: &mut dyn Storage, env: &Env, player: &Addr, client_seed: Option<&str>, wheel: WheelVariant) -> Result<Roll, ContractError> {
+    let seed = roll_seed(storage, env, player, client_seed)?;
+    let mut prng = Prng::new(&seed);
+
+    let result = prng.gen_range(0, wheel.pockets());
+    record_roll(storage, result)?;
+
+    Ok(Roll {
+        result,
+        seed_commitment: seed_commitment(&seed),
+        seed: Binary::from(seed),
+    })
+}
+
+/// Event announcing a roll, with the commitment to its seed and the seed itself. Every roll is
+/// settled in the transaction that makes it, so the seed can't be used to pick a better spin
+fn result_event(roll: &Roll) -> Event {
+    Event::new("wasm-roulette_result")
+        .add_attribute_plaintext("result", pocket_label(roll.result))
+        .add_attribute_plaintext("seed_commitment", roll.seed_commitment.clone())
+        .add_attribute_plaintext("seed", roll.seed.to_base64())
+}
+
+/// Sends `coins` to the player, or credits them to the player's balance when the table pays out by
+/// claim. Credited coins stay reserved until they are claimed
+fn pay_out(storage: &mut dyn Storage, config: &Config, player: &Addr, coins: Vec<Coin>) -> StdResult<Option<BankMsg>> {
+    let coins: Vec<Coin> = coins.into_iter().filter(|coin| !coin.amount.is_zero()).collect();
+
+    if coins.is_empty() {
+        return Ok(None);
+    }
+
+    match config.payout_mode {
+        PayoutMode::Push => Ok(Some(BankMsg::Send { to_address: player.to_string(), amount: coins })),
+        PayoutMode::Claim => {
+            credit_balance(storage, player, &coins)?;
+            reserve_funds(storage, &coins)?;
+
+            Ok(None)
         }
+    }
+}
+
+/// Result event, settlement events and the payout to the player, if there is one.
+///
+/// Every spin ends the transaction, win or lose - otherwise a later message that fails on purpose
+/// could revert the spins that lost
+fn spin_response(storage: &mut dyn Storage, config: &Config, player: &Addr, roll: &Roll, settlement: Settlement) -> StdResult<Response> {
+    let mut coins_to_send: Vec<Coin> = settlement.payouts.into_iter().map(|(denom, amount)| Coin { denom, amount }).collect();
+    coins_to_send.sort_by(|a, b| a.denom.cmp(&b.denom));
+
+    let mut resp = Response::new().add_event(result_event(roll)).add_events(settlement.events);
+
+    if settlement.tripped_circuit_breaker {
+        resp = resp.add_event(circuit_breaker_event());
+    }
+
+    if let Some(msg) = pay_out(storage, config, player, coins_to_send)? {
+        resp = resp.add_message(msg);
+    }
+
+    Ok(resp.add_message(CosmosMsg::finalize_tx()))
+}
+
+fn wrong_spin_mode(reason: &str) -> ContractError {
+    ContractError::WrongSpinMode { reason: reason.to_string() }
+}
+
+fn handle_game_result(mut deps: DepsMut, env: Env, info: MessageInfo, bets: Vec<Bet>, announced_bets: Vec<AnnouncedBet>, client_seed: Option<String>) -> Result<Response, ContractError> {
+
+    deps.api.debug(&format!("Bets are in: {:?} {:?}", bets, announced_bets));
+
+    let config = load_config(deps.storage)?;
+
+    match config.spin_mode {
+        SpinMode::Instant => {}
+        SpinMode::Delayed { .. } => return Err(wrong_spin_mode("Spins on this table are settled later, use place_bets")),
+        SpinMode::Rounds { .. } => return Err(wrong_spin_mode("Bets on this table are placed in rounds, use place_round_bets")),
+    }
+
+    if client_seed.as_ref().is_some_and(|seed| seed.len() > MAX_CLIENT_SEED_LEN) {
+        return Err(ContractError::ClientSeedTooLong { max: MAX_CLIENT_SEED_LEN });
+    }
+
+    validate_bet_slip(deps.branch(), &info, &config, &bets, &announced_bets)?;
+
+    let imprisoned = take_imprisoned_bets(deps.storage, &info.sender)?;
+    let max_payouts = calculate_max_payouts(&bets, &announced_bets, &imprisoned, &config)?;
+    check_bankroll(deps.as_ref(), &env, &max_payouts)?;
+
+    let roll = roll(deps.storage, &env, &info.sender, client_seed.as_deref(), config.wheel)?;
+
+    deps.api.debug(&format!("Roll result: {:?}", roll.result));
+
+    let settlement = settle_bets(deps.storage, &env, &info.sender, bets, announced_bets, imprisoned, roll.result, &config)?;
+
+    deps.api.debug(&format!("payouts to send: {:?}", settlement.payouts));
+
+    Ok(spin_response(deps.storage, &config, &info.sender, &roll, settlement)?)
+}
+
+fn handle_place_bets(mut deps: DepsMut, env: Env, info: MessageInfo, bets: Vec<Bet>, announced_bets: Vec<AnnouncedBet>) -> Result<Response, ContractError> {
+
+    let config = load_config(deps.storage)?;
+
+    let SpinMode::Delayed { delay_blocks, expiry_blocks } = config.spin_mode else {
+        return Err(wrong_spin_mode("Spins on this table aren't delayed"));
+    };
 
-        if bet_amount < config.min_bet {
-            return Err(StdError::generic_err("Bet is lower than table minimum"));
+    let staked = validate_bet_slip(deps.branch(), &info, &config, &bets, &announced_bets)?;
+
+    // the spin takes the held bets with it, so a later spin placed before it settles can't
+    // resolve them against funds that weren't reserved for them
+    let imprisoned = take_imprisoned_bets(deps.storage, &info.sender)?;
+    let max_payouts = calculate_max_payouts(&bets, &announced_bets, &imprisoned, &config)?;
+    check_bankroll(deps.as_ref(), &env, &max_payouts)?;
+
+    // keep the worst case aside so other spins can't be accepted against the same funds
+    let reserved: Vec<Coin> = max_payouts.into_iter().map(|(denom, amount)| Coin { denom, amount }).collect();
+    reserve_funds(deps.storage, &reserved)?;
+
+    let target_height = env.block.height.saturating_add(delay_blocks);
+
+    let spin = PendingSpin {
+        id: next_spin_id(deps.storage)?,
+        player: info.sender,
+        bets,
+        announced_bets,
+        funds: staked,
+        placed_height: env.block.height,
+        target_height,
+        expires_at: target_height.saturating_add(expiry_blocks),
+        reserved,
+        imprisoned,
+    };
+
+    save_pending_spin(deps.storage, &spin)?;
+
+    Ok(Response::new()
+        .add_attribute_plaintext("spin_id", spin.id.to_string())
+        .add_attribute_plaintext("target_height", target_height.to_string())
+    )
+}
+
+fn handle_settle(deps: DepsMut, env: Env, spin_id: u64) -> Result<Response, ContractError> {
+
+    let spin = load_pending_spin(deps.storage, spin_id)?;
+
+    if env.block.height < spin.target_height {
+        return Err(ContractError::SpinNotReady { height: spin.target_height });
+    }
+
+    remove_pending_spin(deps.storage, &spin)?;
+    release_funds(deps.storage, &spin.reserved)?;
+
+    let config = load_config(deps.storage)?;
+
+    if env.block.height > spin.expires_at {
+        let mut resp = Response::new()
+            .add_event(Event::new("wasm-roulette_refund").add_attribute_plaintext("spin_id", spin_id.to_string()));
+
+        // held bets weren't staked on this spin, they go back to prison for the next one
+        imprison_bets(deps.storage, &spin.player, &spin.imprisoned)?;
+
+        if let Some(msg) = pay_out(deps.storage, &config, &spin.player, spin.funds)? {
+            resp = resp.add_message(msg);
         }
 
-        let this_item = coins.get_mut(&b.amount.denom);
+        return Ok(resp);
+    }
+
+    let roll = roll(deps.storage, &env, &spin.player, None, config.wheel)?;
+
+    let settlement = settle_bets(deps.storage, &env, &spin.player, spin.bets, spin.announced_bets, spin.imprisoned, roll.result, &config)?;
+
+    Ok(spin_response(deps.storage, &config, &spin.player, &roll, settlement)?
+        .add_attribute_plaintext("spin_id", spin_id.to_string()))
+}
+
+fn handle_claim(deps: DepsMut, info: MessageInfo, denoms: Option<Vec<String>>) -> Result<Response, ContractError> {
+
+    let balance = load_balance(deps.storage, &info.sender)?;
+
+    let (claimed, kept): (Vec<Coin>, Vec<Coin>) = balance.into_iter()
+        .partition(|coin| match &denoms {
+            Some(denoms) => denoms.contains(&coin.denom),
+            None => true,
+        });
+
+    if claimed.is_empty() {
+        return Err(ContractError::NothingToClaim);
+    }
+
+    save_balance(deps.storage, &info.sender, &kept)?;
+    release_funds(deps.storage, &claimed)?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send { to_address: info.sender.to_string(), amount: claimed })
+    )
+}
+
+const DEFAULT_SETTLE_LIMIT: u32 = 20;
+const MAX_SETTLE_LIMIT: u32 = 50;
+
+fn handle_open_round(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+
+    let config = load_config(deps.storage)?;
+
+    let SpinMode::Rounds { round_blocks } = config.spin_mode else {
+        return Err(wrong_spin_mode("This table doesn't play in rounds"));
+    };
+
+    if let Some(round) = load_latest_round(deps.storage)? {
+        if round.status != RoundStatus::Settled {
+            return Err(ContractError::RoundNotSettled { round_id: round.id });
+        }
+    }
+
+    let round = open_round(deps.storage, env.block.height, env.block.height.saturating_add(round_blocks))?;
+
+    Ok(Response::new()
+        .add_attribute_plaintext("round_id", round.id.to_string())
+        .add_attribute_plaintext("closes_at", round.closes_at.to_string())
+    )
+}
+
+fn handle_place_round_bets(mut deps: DepsMut, env: Env, info: MessageInfo, bets: Vec<Bet>, announced_bets: Vec<AnnouncedBet>) -> Result<Response, ContractError> {
+
+    let config = load_config(deps.storage)?;
+
+    let mut round = match load_latest_round(deps.storage)? {
+        Some(round) if round.status == RoundStatus::Open && env.block.height < round.closes_at => round,
+        _ => return Err(ContractError::NoOpenRound),
+    };
+
+    validate_bet_slip(deps.branch(), &info, &config, &bets, &announced_bets)?;
+
+    // held bets are resolved once per round, so they only count towards the player's first entry
+    let entry = load_round_entry(deps.storage, round.id, &info.sender)?;
+    let imprisoned = match entry {
+        Some(_) => vec![],
+        None => load_imprisoned_bets(deps.storage, &info.sender)?,
+    };
+
+    // the round pays out on a single pocket, so its worst case is the worst pocket over every entry
+    let outcomes = calculate_outcome_payouts(&bets, &announced_bets, &imprisoned, &config)?;
+
+    let mut liabilities = load_round_liabilities(deps.storage, round.id)?;
+    liabilities.resize(outcomes.len(), vec![]);
+
+    for (liability, payouts) in liabilities.iter_mut().zip(outcomes) {
+        let payouts: Vec<Coin> = payouts.into_iter().map(|(denom, amount)| Coin { denom, amount }).collect();
+        add_coins(liability, &payouts)?;
+    }
+
+    let worst_case = max_per_denom(&liabilities.iter()
+        .map(|coins| coins.iter().map(|c| (c.denom.clone(), c.amount)).collect())
+        .collect::<Vec<_>>());
+
+    let to_reserve: HashMap<String, Uint128> = worst_case.into_iter()
+        .map(|(denom, amount)| {
+            let reserved = round.reserved.iter().find(|c| c.denom == denom).map(|c| c.amount).unwrap_or_default();
+            (denom, amount.saturating_sub(reserved))
+        })
+        .filter(|(_, amount)| !amount.is_zero())
+        .collect();
+
+    check_bankroll(deps.as_ref(), &env, &to_reserve)?;
+
+    let to_reserve: Vec<Coin> = to_reserve.into_iter().map(|(denom, amount)| Coin { denom, amount }).collect();
+    reserve_funds(deps.storage, &to_reserve)?;
+    add_coins(&mut round.reserved, &to_reserve)?;
+
+    let mut entry = entry.unwrap_or_else(|| {
+        round.entries += 1;
+        RoundEntry::default()
+    });
+    entry.bets.extend(bets);
+    entry.announced_bets.extend(announced_bets);
+
+    save_round_entry(deps.storage, round.id, &info.sender, &entry)?;
+    save_round_liabilities(deps.storage, round.id, &liabilities)?;
+    save_round(deps.storage, &round)?;
+
+    Ok(Response::new().add_attribute_plaintext("round_id", round.id.to_string()))
+}
+
+fn handle_close_round(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+
+    let config = load_config(deps.storage)?;
+
+    let mut round = match load_latest_round(deps.storage)? {
+        Some(round) if round.status == RoundStatus::Open => round,
+        _ => return Err(ContractError::NoOpenRound),
+    };
+
+    if env.block.height < round.closes_at {
+        return Err(ContractError::RoundNotClosable { height: round.closes_at });
+    }
+
+    // no bet can land in the block that rolls, since betting stops the block before
+    let roll = roll(deps.storage, &env, &info.sender, None, config.wheel)?;
+
+    round.result = Some(roll.result);
+    round.status = if round.entries == 0 { RoundStatus::Settled } else { RoundStatus::Closed };
+    save_round(deps.storage, &round)?;
+
+    // a close that could be reverted would let the closer retry a roll they don't like
+    Ok(Response::new()
+        .add_event(result_event(&roll).add_attribute_plaintext("round_id", round.id.to_string()))
+        .add_message(CosmosMsg::finalize_tx())
+    )
+}
+
+fn handle_settle_round(deps: DepsMut, env: Env, limit: Option<u32>) -> Result<Response, ContractError> {
+
+    let config = load_config(deps.storage)?;
+
+    let (mut round, result) = match load_latest_round(deps.storage)? {
+        Some(round) if round.status == RoundStatus::Closed => match round.result {
+            Some(result) => (round, result),
+            None => return Err(ContractError::NoClosedRound),
+        },
+        _ => return Err(ContractError::NoClosedRound),
+    };
+
+    let limit = limit.unwrap_or(DEFAULT_SETTLE_LIMIT).clamp(1, MAX_SETTLE_LIMIT);
+    let entries = load_round_entries(deps.storage, round.id, round.settled_entries, limit)?;
+
+    let mut resp = Response::new();
+    let mut paid: Vec<Coin> = vec![];
+
+    for (player, entry) in entries {
+        let imprisoned = take_imprisoned_bets(deps.storage, &player)?;
+        let settlement = settle_bets(deps.storage, &env, &player, entry.bets, entry.announced_bets, imprisoned, result, &config)?;
+
+        if settlement.tripped_circuit_breaker {
+            resp = resp.add_event(circuit_breaker_event());
+        }
+
+        let coins_to_send: Vec<Coin> = settlement.payouts.into_iter()
+            .map(|(denom, amount)| Coin { denom, amount })
+            .collect();
+        add_coins(&mut paid, &coins_to_send)?;
+
+        if let Some(msg) = pay_out(deps.storage, &config, &player, coins_to_send)? {
+            resp = resp.add_message(msg);
+        }
+
+        round.settled_entries += 1;
+    }
+
+    // what has been paid out no longer needs to be set aside, and once every entry is paid neither
+    // does the rest
+    let to_release = if round.settled_entries >= round.entries {
+        round.status = RoundStatus::Settled;
+        remove_round_liabilities(deps.storage, round.id);
+        std::mem::take(&mut round.reserved)
+    } else {
+        for coin in &paid {
+            if let Some(r) = round.reserved.iter_mut().find(|r| r.denom == coin.denom) {
+                r.amount = r.amount.saturating_sub(coin.amount);
+            }
+        }
+        paid
+    };
+    release_funds(deps.storage, &to_release)?;
+
+    save_round(deps.storage, &round)?;
+
+    Ok(resp
+        .add_attribute_plaintext("round_id", round.id.to_string())
+        .add_attribute_plaintext("settled_entries", round.settled_entries.to_string())
+    )
+}
+
+/// What a single roll pays back to a player
+struct Settlement {
+    payouts: HashMap<String, Uint128>,
+    events: Vec<Event>,
+    /// Even money bets held for the player's next spin
+    imprisoned: Vec<Bet>,
+    /// Set when this spin pushed the house's losses over the circuit breaker's limit
+    tripped_circuit_breaker: bool,
+}
+
+/// Resolves a player's bets against a roll, including the bets they had held under the en prison
+/// rule that were taken out of prison for it, stores the bets that go to prison on this roll and
+/// adds the spin to the player's history. Announced bets are expected to be validated already
+#[allow(clippy::too_many_arguments)]
+fn settle_bets(
+    storage: &mut dyn Storage,
+    env: &Env,
+    player: &Addr,
+    bets: Vec<Bet>,
+    announced_bets: Vec<AnnouncedBet>,
+    imprisoned: Vec<Bet>,
+    result: u32,
+    config: &Config,
+) -> Result<Settlement, ContractError> {
+    let mut settlement = calculate_settlement(&bets, &announced_bets, &imprisoned, result, config)?;
+
+    // the player's other delayed spins may have held bets of their own since this one was placed
+    imprison_bets(storage, player, &settlement.imprisoned)?;
+
+    let mut payouts: Vec<Coin> = settlement.payouts.iter()
+        .map(|(denom, amount)| Coin { denom: denom.clone(), amount: *amount })
+        .collect();
+    payouts.sort_by(|a, b| a.denom.cmp(&b.denom));
+
+    // held bets were counted as wagered on the spin that imprisoned them
+    let wagered: Vec<Coin> = bets.iter().map(|bet| bet.amount.clone())
+        .chain(announced_bets.iter().map(|announced| announced.amount.clone()))
+        .collect();
+    record_wagers(storage, &wagered, &payouts)?;
+
+    if let Some(breaker) = &config.circuit_breaker {
+        settlement.tripped_circuit_breaker = check_circuit_breaker(storage, env, breaker, &wagered, &payouts)?;
+    }
+
+    push_spin_record(storage, player, SpinRecord {
+        id: 0,
+        block_height: env.block.height,
+        block_time: env.block.time,
+        bets,
+        announced_bets,
+        result,
+        payouts,
+    })?;
+
+    Ok(settlement)
+}
+
+/// Adds a spin to the circuit breaker's window and pauses betting if the house's net loss in the
+/// window has gone over the limit. Returns whether it paused betting
+fn check_circuit_breaker(
+    storage: &mut dyn Storage,
+    env: &Env,
+    breaker: &CircuitBreaker,
+    wagered: &[Coin],
+    paid_out: &[Coin],
+) -> StdResult<bool> {
+    let window = record_loss_window(storage, env.block.height, breaker.window_blocks, wagered, paid_out)?;
+
+    let over_limit = breaker.max_net_loss.iter().any(|limit| {
+        let amount_of = |coins: &[Coin]| coins.iter().find(|c| c.denom == limit.denom).map(|c| c.amount).unwrap_or_default();
+
+        amount_of(&window.paid_out).saturating_sub(amount_of(&window.wagered)) > limit.amount
+    });
+
+    if !over_limit || load_status(storage)? != ContractStatus::Running {
+        return Ok(false);
+    }
+
+    save_status(storage, ContractStatus::BettingPaused)?;
+
+    Ok(true)
+}
+
+fn circuit_breaker_event() -> Event {
+    Event::new("wasm-roulette_circuit_breaker").add_attribute_plaintext("status", "betting_paused")
+}
+
+fn calculate_settlement(
+    bets: &[Bet],
+    announced_bets: &[AnnouncedBet],
+    imprisoned: &[Bet],
+    result: u32,
+    config: &Config,
+) -> Result<Settlement, ContractError> {
+    let wheel = config.wheel;
+
+    let winners = return_winning_numbers(result, wheel)?;
+
+    let mut winning_bets = vec![];
+    let mut losing_bets = vec![];
+    for bet in bets {
+        if winners.contains(&bet.result) {
+            winning_bets.push(bet)
+        } else {
+            losing_bets.push(bet)
+        }
+    }
+
+    let mut payouts: HashMap<String, Uint128> = std::collections::HashMap::new();
+
+    let mut winning_bets_evt = Event::new("winners");
+
+    for win_bet in winning_bets {
+        let payout_amount = payout_of(win_bet.amount.amount, config.payouts.multiplier(&win_bet.result))?;
+
+        winning_bets_evt = winning_bets_evt.add_attribute_plaintext(win_bet.result.clone(), payout_amount);
+
+        add_payout(&mut payouts, &win_bet.amount.denom, payout_amount)?;
+    }
+
+    // announced bets are reported as a single bet, with the total paid out over all their chips
+    let mut announced_evt = Event::new("announced");
+
+    for announced in announced_bets {
+        let expanded = announced.expand(wheel)
+            .map_err(|reason| ContractError::InvalidBet { bet: String::from(announced.call.clone()), reason })?;
+
+        let payout_amount = expanded.iter()
+            .filter(|bet| winners.contains(&bet.result))
+            .try_fold(Uint128::zero(), |total, bet| -> Result<Uint128, ContractError> {
+                Ok(total.checked_add(payout_of(bet.amount.amount, config.payouts.multiplier(&bet.result))?)?)
+            })?;
+
+        if payout_amount.is_zero() {
+            continue;
+        }
+
+        announced_evt = announced_evt.add_attribute_plaintext(announced.call.clone(), payout_amount);
+
+        add_payout(&mut payouts, &announced.amount.denom, payout_amount)?;
+    }
+
+    // even money bets held from the player's previous spin are returned if they win this one
+    let mut prison_evt = Event::new("en_prison");
+    let mut imprisoned_bets = vec![];
+
+    for bet in imprisoned {
+        if !wheel.is_zero(result) && winners.contains(&bet.result) {
+            prison_evt = prison_evt.add_attribute_plaintext("released", bet.result.clone());
+            add_payout(&mut payouts, &bet.amount.denom, bet.amount.amount)?;
+        } else {
+            prison_evt = prison_evt.add_attribute_plaintext("lost", bet.result.clone());
+        }
+    }
+
+    let mut partage_evt = Event::new("la_partage");
+
+    if wheel.is_zero(result) {
+        for bet in losing_bets.into_iter().filter(|b| b.result.is_even_money()) {
+            match config.zero_rule {
+                ZeroRule::None => {}
+                ZeroRule::LaPartage => {
+                    let refund = bet.amount.amount.multiply_ratio(1u128, 2u128);
+
+                    partage_evt = partage_evt.add_attribute_plaintext(bet.result.clone(), refund);
+                    add_payout(&mut payouts, &bet.amount.denom, refund)?;
+                }
+                ZeroRule::EnPrison => {
+                    prison_evt = prison_evt.add_attribute_plaintext("imprisoned", bet.result.clone());
+                    imprisoned_bets.push(bet.clone());
+                }
+            }
+        }
+    }
+
+    let events = [winning_bets_evt, announced_evt, partage_evt, prison_evt]
+        .into_iter()
+        .filter(|evt| !evt.attributes.is_empty())
+        .collect();
+
+    Ok(Settlement { payouts, events, imprisoned: imprisoned_bets, tripped_circuit_breaker: false })
+}
+
+/// A stake times its payout multiplier
+fn payout_of(stake: Uint128, multiplier: Decimal) -> Result<Uint128, ContractError> {
+    stake.checked_multiply_ratio(multiplier.atomics(), Decimal::one().atomics()).map_err(|_| ContractError::Overflow)
+}
+
+fn add_payout(payouts: &mut HashMap<String, Uint128>, denom: &str, amount: Uint128) -> Result<(), ContractError> {
+    let total = payouts.entry(denom.to_string()).or_default();
+    *total = total.checked_add(amount)?;
+
+    Ok(())
+}
+
+/// What the bets would pay out on each pocket of the wheel, indexed by pocket
+fn calculate_outcome_payouts(
+    bets: &[Bet],
+    announced_bets: &[AnnouncedBet],
+    imprisoned: &[Bet],
+    config: &Config,
+) -> Result<Vec<HashMap<String, Uint128>>, ContractError> {
+    (0..config.wheel.pockets())
+        .map(|result| Ok(calculate_settlement(bets, announced_bets, imprisoned, result, config)?.payouts))
+        .collect()
+}
+
+/// Largest payout of every denom over all the pockets
+fn max_per_denom(outcomes: &[HashMap<String, Uint128>]) -> HashMap<String, Uint128> {
+    let mut max_payouts: HashMap<String, Uint128> = HashMap::default();
+
+    for payouts in outcomes {
+        for (denom, amount) in payouts {
+            let max = max_payouts.entry(denom.clone()).or_default();
+            *max = (*max).max(*amount);
+        }
+    }
+
+    max_payouts
+}
+
+/// The most the table could have to pay out on a single roll for these bets, per denom
+fn calculate_max_payouts(
+    bets: &[Bet],
+    announced_bets: &[AnnouncedBet],
+    imprisoned: &[Bet],
+    config: &Config,
+) -> Result<HashMap<String, Uint128>, ContractError> {
+    Ok(max_per_denom(&calculate_outcome_payouts(bets, announced_bets, imprisoned, config)?))
+}
+
+/// Makes sure the contract holds enough of every denom to pay out the worst case roll, on top of
+/// what is already set aside for pending spins. The player's funds have already been transferred
+/// to the contract at this point, so they count towards the bankroll
+fn check_bankroll(deps: Deps, env: &Env, max_payouts: &HashMap<String, Uint128>) -> Result<(), ContractError> {
+    for (denom, amount) in max_payouts {
+        let balance = deps.querier.query_balance(&env.contract.address, denom)?;
+        let reserved = load_reserved_amount(deps.storage, denom)?;
+        let available = Coin { denom: denom.clone(), amount: balance.amount.saturating_sub(reserved) };
+
+        if available.amount < *amount {
+            return Err(ContractError::InsufficientBankroll { required: Coin { denom: denom.clone(), amount: *amount }, available });
+        }
+    }
+
+    Ok(())
+}
+
+
+#[entry_point]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Round { round_id } =>
+            to_binary(&query_round(deps, round_id)?),
+        QueryMsg::Stats {} =>
+            to_binary(&query_stats(deps)?),
+        QueryMsg::Config {} =>
+            to_binary(&query_config(deps)?),
+        QueryMsg::PendingAdmin {} =>
+            to_binary(&query_pending_admin(deps)?),
+        QueryMsg::Roles {} =>
+            to_binary(&query_roles(deps)?),
+        QueryMsg::Status {} =>
+            to_binary(&StatusResponse { status: load_status(deps.storage)? }),
+        QueryMsg::PendingSpins { address, key, page, page_size } => {
+            check_viewing_key(deps, &address, &key)?;
+            to_binary(&query_pending_spins(deps, address, page, page_size)?)
+        }
+        QueryMsg::History { address, key, start_after, limit } => {
+            check_viewing_key(deps, &address, &key)?;
+            to_binary(&query_history(deps, address, start_after, limit)?)
+        }
+        QueryMsg::Balance { address, key } => {
+            check_viewing_key(deps, &address, &key)?;
+            to_binary(&query_balance(deps, address)?)
+        }
+        QueryMsg::WithPermit { permit, query } =>
+            permit_query(deps, &env, permit, query),
+    }
+}
+
+fn permit_query(deps: Deps, env: &Env, permit: Permit, query: QueryWithPermit) -> StdResult<Binary> {
+    match query {
+        QueryWithPermit::PendingSpins { page, page_size } => {
+            let account = validate_permit(deps, env, &permit, Permission::PendingSpins)?;
+            to_binary(&query_pending_spins(deps, account, page, page_size)?)
+        }
+        QueryWithPermit::History { start_after, limit } => {
+            let account = validate_permit(deps, env, &permit, Permission::History)?;
+            to_binary(&query_history(deps, account, start_after, limit)?)
+        }
+        QueryWithPermit::Balance {} => {
+            let account = validate_permit(deps, env, &permit, Permission::Balance)?;
+            to_binary(&query_balance(deps, account)?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    
+    use super::*;
+
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_dependencies_with_balance, mock_env, mock_info,
+    };
+    use cosmwasm_std::{Addr, coins, ContractInfoResponse, Decimal, from_binary, StdError, TransactionInfo};
+    use crate::auth::{permit_sign_bytes, pubkey_to_account, PermitParams, PermitSignature, PubKey, VIEWING_KEY_PREFIX};
+    use crate::msg::{BalanceResponse, ConfigResponse, DenomStats, HistoryResponse, PendingAdminResponse, PendingSpinsResponse, RolesResponse, RoundResponse, StatsResponse, StatusResponse};
+    use crate::migrations::{LEGACY_CONFIG_ITEM, LegacyConfig};
+    use crate::state::{load_reserved, load_round, Round, save_imprisoned_bets};
+    use std::collections::HashMap;
+    use crate::contract::return_winning_numbers;
+    use crate::types::{AnnouncedCall, BetCategory, CategoryLimits, DenomLimits, DOUBLE_ZERO, PayoutTable, TRIPLE_ZERO};
+    // Just set sender and funds for the message.
+    // This is intended for use in test code only.
+
+    fn instantiate_contract(deps: DepsMut) -> MessageInfo {
+        let msg = InstantiateMsg { supported_denoms: Some(vec!["token".to_string()]), ..Default::default() };
+        let info = mock_info("creator", &coins(200, "token"));
+        let _res = instantiate(deps, mock_env(), info.clone(), msg).unwrap();
+        info
+    }
+
+    /// The config a table gets with nothing but its denoms set
+    fn test_config() -> Config {
+        Config {
+            min_bet: Uint128::zero(),
+            max_bet: Uint128::MAX,
+            max_total: Uint128::MAX,
+            supported_denoms: vec!["abc".to_string(), "def".to_string()],
+            denom_limits: vec![],
+            wheel: WheelVariant::SingleZero,
+            payouts: PayoutTable::default(),
+            zero_rule: ZeroRule::None,
+            spin_mode: SpinMode::Instant,
+            circuit_breaker: None,
+            contract_callers: ContractCallers::Allowed,
+            payout_mode: PayoutMode::Push,
+        }
+    }
+
+    #[test]
+    fn new_game_loser() {
+        let mut deps = mock_dependencies_with_balance(&coins(1_000_000, "token"));
+        let _env = mock_env();
+
+        let info = instantiate_contract(deps.as_mut());
+
+        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Red };
+
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet], announced_bets: None, client_seed: None};
+
+        let res = execute(deps.as_mut(), mock_env(), info, execute_msg).unwrap();
+
+        // nothing to pay, but the loss can't be reverted either
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, CosmosMsg::finalize_tx());
+    }
+
+    #[test]
+    fn bet_slip_errors() {
+        let mut deps = mock_dependencies_with_balance(&coins(1_000_000, "token"));
+        let info = instantiate_contract(deps.as_mut());
+
+        let bet = |amount: u128, denom: &str| Bet{ amount: Coin::new(amount, denom), result: GameResult::Red };
+        let execute_msg = |bets: Vec<Bet>| ExecuteMsg::Bet {bets, announced_bets: None, client_seed: None};
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &coins(200, "uscrt")), execute_msg(vec![bet(200, "uscrt")])).unwrap_err();
+        assert_eq!(err, ContractError::UnsupportedDenom { denom: "uscrt".to_string() });
+
+        let err = execute(deps.as_mut(), mock_env(), info, execute_msg(vec![bet(150, "token")])).unwrap_err();
+        assert_eq!(err, ContractError::FundsMismatch { denom: "token".to_string(), expected: Uint128::new(150), sent: Uint128::new(200) });
+    }
+
+    #[test]
+    fn new_game_winner() {
+        let mut deps = mock_dependencies_with_balance(&coins(1_000_000, "token"));
+        let _env = mock_env();
+
+        let info = instantiate_contract(deps.as_mut());
+
+        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Line {nums: (26, 29)} };
+
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet], announced_bets: None, client_seed: None};
+
+        let res = execute(deps.as_mut(), mock_env(), info, execute_msg).unwrap();
+
+        // bank send + last message marker
+        assert_eq!(res.messages.len(), 2)
+    }
+
+    #[test]
+    fn new_game_winner_custom_payouts() {
+        let mut deps = mock_dependencies_with_balance(&coins(1_000_000, "token"));
+
+        let payouts = PayoutTable { split: Decimal::percent(1750), ..PayoutTable::default() };
+        let msg = InstantiateMsg { supported_denoms: Some(vec!["token".to_string()]), payouts: Some(payouts), ..Default::default() };
+        let info = mock_info("creator", &coins(200, "token"));
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Line {nums: (26, 29)} };
+
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet], announced_bets: None, client_seed: None};
+
+        let res = execute(deps.as_mut(), mock_env(), info, execute_msg).unwrap();
+
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: "creator".to_string(),
+            amount: coins(3500, "token"),
+        }));
+    }
+
+    #[test]
+    fn zero_rule_la_partage() {
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        let mut config = load_config(&deps.storage).unwrap();
+        config.zero_rule = ZeroRule::LaPartage;
+
+        let player = Addr::unchecked("player");
+        let bets = vec![
+            Bet { amount: Coin::new(100, "token"), result: GameResult::Red },
+            Bet { amount: Coin::new(10, "token"), result: GameResult::Exact { num: 0 } },
+            Bet { amount: Coin::new(50, "token"), result: GameResult::Range1to12 },
+        ];
+
+        let settlement = settle_bets(&mut deps.storage, &mock_env(), &player, bets.clone(), vec![], vec![], 0, &config).unwrap();
+        assert_eq!(settlement.payouts.get("token"), Some(&Uint128::from(410u128)));
+
+        // only applies to zero
+        let settlement = settle_bets(&mut deps.storage, &mock_env(), &player, bets, vec![], vec![], 2, &config).unwrap();
+        assert_eq!(settlement.payouts.get("token"), Some(&Uint128::from(150u128)));
+    }
+
+    #[test]
+    fn zero_rule_en_prison() {
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        let mut config = load_config(&deps.storage).unwrap();
+        config.zero_rule = ZeroRule::EnPrison;
+
+        let player = Addr::unchecked("player");
+        let bets = vec![
+            Bet { amount: Coin::new(100, "token"), result: GameResult::Red },
+            Bet { amount: Coin::new(20, "token"), result: GameResult::Odd },
+            Bet { amount: Coin::new(10, "token"), result: GameResult::Range1to12 },
+        ];
+
+        let held = take_imprisoned_bets(&mut deps.storage, &player).unwrap();
+        let settlement = settle_bets(&mut deps.storage, &mock_env(), &player, bets.clone(), vec![], held, 0, &config).unwrap();
+        assert!(settlement.payouts.is_empty());
+        assert_eq!(load_imprisoned_bets(&deps.storage, &player).unwrap(), bets[..2].to_vec());
+
+        // 1 is red and odd, so both bets are released with their stake only
+        let held = take_imprisoned_bets(&mut deps.storage, &player).unwrap();
+        let settlement = settle_bets(&mut deps.storage, &mock_env(), &player, vec![], vec![], held, 1, &config).unwrap();
+        assert_eq!(settlement.payouts.get("token"), Some(&Uint128::from(120u128)));
+        assert!(load_imprisoned_bets(&deps.storage, &player).unwrap().is_empty());
+
+        // a second zero loses the imprisoned bets, and locks up the new ones
+        let held = take_imprisoned_bets(&mut deps.storage, &player).unwrap();
+        settle_bets(&mut deps.storage, &mock_env(), &player, bets.clone(), vec![], held, 0, &config).unwrap();
+        let held = take_imprisoned_bets(&mut deps.storage, &player).unwrap();
+        let settlement = settle_bets(&mut deps.storage, &mock_env(), &player, bets[..1].to_vec(), vec![], held, 0, &config).unwrap();
+        assert!(settlement.payouts.is_empty());
+        assert_eq!(load_imprisoned_bets(&deps.storage, &player).unwrap(), bets[..1].to_vec());
+
+        // and a losing spin loses them too
+        let held = take_imprisoned_bets(&mut deps.storage, &player).unwrap();
+        let settlement = settle_bets(&mut deps.storage, &mock_env(), &player, vec![], vec![], held, 2, &config).unwrap();
+        assert!(settlement.payouts.is_empty());
+        assert!(load_imprisoned_bets(&deps.storage, &player).unwrap().is_empty());
+
+        // other players are not affected
+        assert!(load_imprisoned_bets(&deps.storage, &Addr::unchecked("creator")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_announced_bets_expand() {
+        let wheel = WheelVariant::SingleZero;
+
+        for (call, chips) in [
+            (AnnouncedCall::VoisinsDuZero, 9),
+            (AnnouncedCall::TiersDuCylindre, 6),
+            (AnnouncedCall::Orphelins, 5),
+            (AnnouncedCall::JeuZero, 4),
+            (AnnouncedCall::Neighbours { num: 0, count: 2 }, 5),
+        ] {
+            let announced = AnnouncedBet { amount: Coin::new(chips * 10, "token"), call: call.clone() };
+            let bets = announced.expand(wheel).unwrap();
+
+            let total: u128 = bets.iter().map(|b| b.amount.amount.u128()).sum();
+            assert_eq!(total, chips * 10);
+            assert!(bets.iter().all(|b| b.result.validate(wheel).is_ok()), "{:?}", call);
+
+            let uneven = AnnouncedBet { amount: Coin::new(chips * 10 + 1, "token"), call };
+            assert!(uneven.expand(wheel).is_err());
+        }
+
+        let neighbours = AnnouncedBet { amount: Coin::new(5, "token"), call: AnnouncedCall::Neighbours { num: 0, count: 2 } };
+        let nums: Vec<GameResult> = neighbours.expand(wheel).unwrap().into_iter().map(|b| b.result).collect();
+        assert_eq!(nums, vec![
+            GameResult::Exact { num: 3 },
+            GameResult::Exact { num: 26 },
+            GameResult::Exact { num: 0 },
+            GameResult::Exact { num: 32 },
+            GameResult::Exact { num: 15 },
+        ]);
+
+        assert!(neighbours.expand(WheelVariant::DoubleZero).is_err());
+        let too_many = AnnouncedBet { amount: Coin::new(39, "token"), call: AnnouncedCall::Neighbours { num: 0, count: 19 } };
+        assert!(too_many.expand(wheel).is_err());
+    }
+
+    #[test]
+    fn test_announced_bets_settle() {
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        let config = load_config(&deps.storage).unwrap();
+        let player = Addr::unchecked("player");
+
+        let announced = vec![
+            AnnouncedBet { amount: Coin::new(40, "token"), call: AnnouncedCall::JeuZero },
+            AnnouncedBet { amount: Coin::new(60, "token"), call: AnnouncedCall::TiersDuCylindre },
+        ];
+
+        // 26 is a straight up in jeu zero
+        let settlement = settle_bets(&mut deps.storage, &mock_env(), &player, vec![], announced.clone(), vec![], 26, &config).unwrap();
+        assert_eq!(settlement.payouts.get("token"), Some(&Uint128::from(360u128)));
+        assert_eq!(settlement.events, vec![
+            Event::new("announced").add_attribute_plaintext("jeu-zero", "360"),
+        ]);
+
+        // 12 is covered by the 12-15 split of jeu zero, but not by tiers
+        let settlement = settle_bets(&mut deps.storage, &mock_env(), &player, vec![], announced, vec![], 12, &config).unwrap();
+        assert_eq!(settlement.payouts.get("token"), Some(&Uint128::from(180u128)));
+    }
+
+    #[test]
+    fn new_game_bankroll_too_small() {
+        // the 200 sent with the bet is part of the bankroll
+        let mut deps = mock_dependencies_with_balance(&coins(7199, "token"));
+
+        let info = instantiate_contract(deps.as_mut());
+
+        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Exact { num: 17 } };
+
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet.clone()], announced_bets: None, client_seed: None};
+
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), execute_msg).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientBankroll { required: Coin::new(7200, "token"), available: Coin::new(7199, "token") });
+
+        let mut deps = mock_dependencies_with_balance(&coins(7200, "token"));
+        instantiate_contract(deps.as_mut());
+
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet], announced_bets: None, client_seed: None};
+        assert!(execute(deps.as_mut(), mock_env(), info, execute_msg).is_ok());
+    }
+
+    #[test]
+    fn test_calculate_max_payouts() {
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        let mut config = load_config(&deps.storage).unwrap();
+
+        // red and black can't both win, but a straight up and its colour can
+        let bets = vec![
+            Bet { amount: Coin::new(100, "token"), result: GameResult::Red },
+            Bet { amount: Coin::new(100, "token"), result: GameResult::Black },
+            Bet { amount: Coin::new(10, "token"), result: GameResult::Exact { num: 1 } },
+            Bet { amount: Coin::new(10, "other"), result: GameResult::Exact { num: 0 } },
+        ];
+
+        let max = calculate_max_payouts(&bets, &[], &[], &config).unwrap();
+        assert_eq!(max.get("token"), Some(&Uint128::from(560u128)));
+        assert_eq!(max.get("other"), Some(&Uint128::from(360u128)));
+
+        // bets held in prison can be released on top of that
+        config.zero_rule = ZeroRule::EnPrison;
+        let imprisoned = vec![Bet { amount: Coin::new(50, "token"), result: GameResult::Odd }];
+
+        let max = calculate_max_payouts(&bets, &[], &imprisoned, &config).unwrap();
+        assert_eq!(max.get("token"), Some(&Uint128::from(610u128)));
+    }
+
+    #[test]
+    fn new_game_uneven_announced_bet() {
+        let mut deps = mock_dependencies();
+
+        let info = instantiate_contract(deps.as_mut());
+
+        let announced = AnnouncedBet { amount: Coin::new(200, "token"), call: AnnouncedCall::VoisinsDuZero };
+
+        let execute_msg = ExecuteMsg::Bet {bets: vec![], announced_bets: Some(vec![announced]), client_seed: None};
+
+        let err = execute(deps.as_mut(), mock_env(), info, execute_msg).unwrap_err();
+
+        assert_eq!(err, ContractError::InvalidBet { bet: "voisins".to_string(), reason: "a stake of 200token can't be split evenly over 9 chips".to_string() });
+    }
+
+    fn instantiate_delayed(deps: DepsMut) -> MessageInfo {
+        let msg = InstantiateMsg { supported_denoms: Some(vec!["token".to_string()]), spin_mode: Some(SpinMode::Delayed { delay_blocks: 2, expiry_blocks: 10 }), ..Default::default() };
+        let info = mock_info("creator", &coins(200, "token"));
+        instantiate(deps, mock_env(), info.clone(), msg).unwrap();
+        info
+    }
+
+    fn env_at_height(height: u64) -> Env {
+        let mut env = mock_env();
+        env.block.height = height;
+        env
+    }
+
+    /// Sets `address`'s viewing key to "key"
+    fn set_viewing_key(deps: DepsMut, address: &str) {
+        execute(deps, mock_env(), mock_info(address, &[]), ExecuteMsg::SetViewingKey { key: "key".to_string() }).unwrap();
+    }
+
+    fn pending_spins(mut deps: DepsMut, address: &str) -> Vec<PendingSpin> {
+        set_viewing_key(deps.branch(), address);
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::PendingSpins { address: Addr::unchecked(address), key: "key".to_string(), page: None, page_size: None }).unwrap();
+        from_binary::<PendingSpinsResponse>(&res).unwrap().spins
+    }
+
+    #[test]
+    fn delayed_place_and_settle() {
+        let mut deps = mock_dependencies_with_balance(&coins(10_000, "token"));
+        let info = instantiate_delayed(deps.as_mut());
+        let height = mock_env().block.height;
+
+        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Exact { num: 17 } };
+
+        // instant spins are turned off
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet.clone()], announced_bets: None, client_seed: None};
+        assert!(matches!(execute(deps.as_mut(), mock_env(), info.clone(), execute_msg).unwrap_err(), ContractError::WrongSpinMode { .. }));
+
+        let execute_msg = ExecuteMsg::PlaceBets {bets: vec![bet], announced_bets: None};
+        let res = execute(deps.as_mut(), mock_env(), info, execute_msg).unwrap();
+        assert!(res.messages.is_empty());
+
+        let spins = pending_spins(deps.as_mut(), "creator");
+        assert_eq!(spins.len(), 1);
+        assert_eq!(spins[0].id, 1);
+        assert_eq!(spins[0].target_height, height + 2);
+        assert_eq!(spins[0].expires_at, height + 12);
+        assert_eq!(load_reserved(&deps.storage).unwrap(), coins(7200, "token"));
+
+        // can't be settled on the block the bets were placed in or before the target
+        let anyone = mock_info("anyone", &[]);
+        assert!(execute(deps.as_mut(), env_at_height(height), anyone.clone(), ExecuteMsg::Settle { spin_id: 1 }).is_err());
+        let res = execute(deps.as_mut(), env_at_height(height + 1), anyone.clone(), ExecuteMsg::Settle { spin_id: 1 });
+        assert_eq!(res.unwrap_err(), ContractError::SpinNotReady { height: height + 2 });
+
+        let res = execute(deps.as_mut(), env_at_height(height + 2), anyone.clone(), ExecuteMsg::Settle { spin_id: 1 }).unwrap();
+        assert_eq!(res.events[0].ty, "wasm-roulette_result");
+        assert_eq!(res.messages.last().unwrap().msg, CosmosMsg::finalize_tx());
+
+        assert!(pending_spins(deps.as_mut(), "creator").is_empty());
+        assert!(load_reserved(&deps.storage).unwrap().is_empty());
+
+        // already settled
+        let res = execute(deps.as_mut(), env_at_height(height + 3), anyone, ExecuteMsg::Settle { spin_id: 1 });
+        assert_eq!(res.unwrap_err(), ContractError::SpinNotFound { spin_id: 1 });
+    }
+
+    #[test]
+    fn delayed_expired_spin_refunded() {
+        let mut deps = mock_dependencies_with_balance(&coins(10_000, "token"));
+        let info = instantiate_delayed(deps.as_mut());
+        let height = mock_env().block.height;
+
+        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Red };
+
+        let execute_msg = ExecuteMsg::PlaceBets {bets: vec![bet], announced_bets: None};
+        execute(deps.as_mut(), mock_env(), info, execute_msg).unwrap();
+
+        let res = execute(deps.as_mut(), env_at_height(height + 13), mock_info("anyone", &[]), ExecuteMsg::Settle { spin_id: 1 }).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: "creator".to_string(),
+            amount: coins(200, "token"),
+        }));
+        assert!(load_reserved(&deps.storage).unwrap().is_empty());
+    }
+
+    #[test]
+    fn delayed_reserved_funds() {
+        let mut deps = mock_dependencies_with_balance(&coins(10_000, "token"));
+        let info = instantiate_delayed(deps.as_mut());
+
+        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Exact { num: 17 } };
+
+        let execute_msg = ExecuteMsg::PlaceBets {bets: vec![bet.clone()], announced_bets: None};
+        execute(deps.as_mut(), mock_env(), info.clone(), execute_msg.clone()).unwrap();
+
+        // 7200 of the 10000 is set aside for the first spin
+        assert!(execute(deps.as_mut(), mock_env(), info.clone(), execute_msg).is_err());
+
+        let withdraw = ExecuteMsg::AdminWithdraw {coin: Coin::new(2801, "token")};
+        assert_eq!(execute(deps.as_mut(), mock_env(), info.clone(), withdraw).unwrap_err(), ContractError::FundsReserved { available: Coin::new(2800, "token") });
+
+        let withdraw = ExecuteMsg::AdminWithdraw {coin: Coin::new(2800, "token")};
+        assert!(execute(deps.as_mut(), mock_env(), info, withdraw).is_ok());
+    }
+
+    #[test]
+    fn delayed_spins_share_no_imprisoned_bets() {
+        let mut deps = mock_dependencies_with_balance(&coins(100_000, "token"));
+        let msg = InstantiateMsg { supported_denoms: Some(vec!["token".to_string()]), zero_rule: Some(ZeroRule::EnPrison), spin_mode: Some(SpinMode::Delayed { delay_blocks: 2, expiry_blocks: 10 }), ..Default::default() };
+        let info = mock_info("creator", &coins(100, "token"));
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let height = mock_env().block.height;
+
+        // a zero puts the player's odd bet in prison
+        let config = load_config(&deps.storage).unwrap();
+        let held = Bet { amount: Coin::new(100, "token"), result: GameResult::Odd };
+        settle_bets(&mut deps.storage, &mock_env(), &info.sender, vec![held.clone()], vec![], vec![], 0, &config).unwrap();
+
+        // the first spin takes the held bet along, the second one placed before it settles doesn't
+        let bet = Bet { amount: Coin::new(100, "token"), result: GameResult::Black };
+        let place = ExecuteMsg::PlaceBets { bets: vec![bet], announced_bets: None };
+        execute(deps.as_mut(), mock_env(), info.clone(), place.clone()).unwrap();
+        execute(deps.as_mut(), mock_env(), info.clone(), place.clone()).unwrap();
+
+        let spins = pending_spins(deps.as_mut(), "creator");
+        assert_eq!(spins[0].imprisoned, vec![held.clone()]);
+        assert_eq!(spins[0].reserved, coins(300, "token"));
+        assert!(spins[1].imprisoned.is_empty());
+        assert_eq!(spins[1].reserved, coins(200, "token"));
+        assert!(load_imprisoned_bets(&deps.storage, &info.sender).unwrap().is_empty());
+
+        let in_prison_event = |res: &Response| res.events.iter().any(|e| e.ty == "en_prison");
+
+        // settled out of order, only the spin that reserved for the held bet resolves it
+        let anyone = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), env_at_height(height + 2), anyone.clone(), ExecuteMsg::Settle { spin_id: 2 }).unwrap();
+        assert!(!in_prison_event(&res));
+        let res = execute(deps.as_mut(), env_at_height(height + 2), anyone.clone(), ExecuteMsg::Settle { spin_id: 1 }).unwrap();
+        assert!(in_prison_event(&res));
+        assert!(load_reserved(&deps.storage).unwrap().is_empty());
+
+        // an expired spin hands its held bet back to prison
+        save_imprisoned_bets(&mut deps.storage, &info.sender, &vec![held.clone()]).unwrap();
+        execute(deps.as_mut(), mock_env(), info.clone(), place).unwrap();
+        assert!(load_imprisoned_bets(&deps.storage, &info.sender).unwrap().is_empty());
+
+        let res = execute(deps.as_mut(), env_at_height(height + 13), anyone, ExecuteMsg::Settle { spin_id: 3 }).unwrap();
+        assert_eq!(res.events[0].ty, "wasm-roulette_refund");
+        assert_eq!(load_imprisoned_bets(&deps.storage, &info.sender).unwrap(), vec![held]);
+        assert!(load_reserved(&deps.storage).unwrap().is_empty());
+    }
+
+    #[test]
+    fn roll_seed_unique_per_spin() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let alice = Addr::unchecked("alice");
+
+        // same block, same player - the nonce still moves the seed
+        let first = roll_seed(&mut deps.storage, &env, &alice, None).unwrap();
+        let second = roll_seed(&mut deps.storage, &env, &alice, None).unwrap();
+        assert_ne!(first, second);
+
+        let mut other_tx = env.clone();
+        other_tx.transaction = Some(TransactionInfo { index: 1, hash: String::new() });
+        let bob = roll_seed(&mut deps.storage, &other_tx, &Addr::unchecked("bob"), None).unwrap();
+        assert_ne!(second, bob);
+
+        // a block with many spins doesn't hand them all the same result
+        let results: Vec<u32> = (0..20).map(|_| roll(&mut deps.storage, &env, &alice, None, WheelVariant::SingleZero).unwrap().result).collect();
+        assert!(results.iter().any(|result| *result != results[0]));
+
+        let mut no_random = env;
+        no_random.block.random = None;
+        assert_eq!(roll_seed(&mut deps.storage, &no_random, &alice, None).unwrap_err(), ContractError::RandomnessUnavailable);
+    }
+
+    #[test]
+    fn client_seed_in_roll() {
+        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Red };
+
+        let commitment = |client_seed: Option<String>| {
+            let mut deps = mock_dependencies_with_balance(&coins(1_000_000, "token"));
+            let info = instantiate_contract(deps.as_mut());
+
+            let execute_msg = ExecuteMsg::Bet {bets: vec![bet.clone()], announced_bets: None, client_seed};
+            let res = execute(deps.as_mut(), mock_env(), info, execute_msg).unwrap();
+
+            let event = res.events.iter().find(|e| e.ty == "wasm-roulette_result").unwrap();
+            event.attributes.iter().find(|a| a.key == "seed_commitment").unwrap().value.clone()
+        };
+
+        // the commitment is a hash of the exact seed, so it moves with the client seed
+        let lucky = commitment(Some("lucky".to_string()));
+        assert_eq!(lucky, commitment(Some("lucky".to_string())));
+        assert_ne!(lucky, commitment(Some("unlucky".to_string())));
+        assert_ne!(lucky, commitment(None));
+
+        let mut deps = mock_dependencies();
+        let seed = roll_seed(&mut deps.storage, &mock_env(), &Addr::unchecked("creator"), Some("lucky")).unwrap();
+        assert_eq!(lucky, seed_commitment(&seed));
+
+        // and never the key the prng was seeded with
+        assert_ne!(lucky, Binary::from(Sha256::digest(&seed).as_slice()).to_base64());
+
+        // the revealed seed matches the commitment and replays to the same pocket
+        let mut deps = mock_dependencies_with_balance(&coins(1_000_000, "token"));
+        let info = instantiate_contract(deps.as_mut());
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet.clone()], announced_bets: None, client_seed: None};
+        let res = execute(deps.as_mut(), mock_env(), info, execute_msg).unwrap();
+
+        let event = res.events.iter().find(|e| e.ty == "wasm-roulette_result").unwrap();
+        let attribute = |key: &str| event.attributes.iter().find(|a| a.key == key).unwrap().value.clone();
+        let seed = Binary::from_base64(&attribute("seed")).unwrap();
+        assert_eq!(seed_commitment(&seed), attribute("seed_commitment"));
+        assert_eq!(pocket_label(Prng::new(&seed).gen_range(0, WheelVariant::SingleZero.pockets())), attribute("result"));
+
+        let mut deps = mock_dependencies_with_balance(&coins(1_000_000, "token"));
+        let info = instantiate_contract(deps.as_mut());
+
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet], announced_bets: None, client_seed: Some("x".repeat(MAX_CLIENT_SEED_LEN + 1))};
+        assert_eq!(execute(deps.as_mut(), mock_env(), info, execute_msg).unwrap_err(), ContractError::ClientSeedTooLong { max: MAX_CLIENT_SEED_LEN });
+    }
+
+    fn history(mut deps: DepsMut, address: &str, start_after: Option<u32>, limit: Option<u32>) -> HistoryResponse {
+        set_viewing_key(deps.branch(), address);
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::History { address: Addr::unchecked(address), key: "key".to_string(), start_after, limit }).unwrap();
+        from_binary(&res).unwrap()
+    }
+
+    #[test]
+    fn history_recorded() {
+        let mut deps = mock_dependencies_with_balance(&coins(1_000_000, "token"));
+        let info = instantiate_contract(deps.as_mut());
+
+        assert_eq!(history(deps.as_mut(), "creator", None, None).total, 0);
+
+        // the mock roll lands on 26
+        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Exact { num: 26 } };
+        for height in 0..3 {
+            let execute_msg = ExecuteMsg::Bet {bets: vec![bet.clone()], announced_bets: None, client_seed: None};
+            execute(deps.as_mut(), env_at_height(12_345 + height), info.clone(), execute_msg).unwrap();
+        }
+
+        let res = history(deps.as_mut(), "creator", None, Some(2));
+        assert_eq!(res.total, 3);
+        assert_eq!(res.spins.len(), 2);
+        assert_eq!(res.spins[0].id, 0);
+        assert_eq!(res.spins[0].block_height, 12_345);
+        assert_eq!(res.spins[0].block_time, mock_env().block.time);
+        assert_eq!(res.spins[0].bets, vec![bet]);
+        assert_eq!(res.spins[0].result, 26);
+        assert_eq!(res.spins[0].payouts, coins(7200, "token"));
+
+        let res = history(deps.as_mut(), "creator", Some(1), Some(2));
+        assert_eq!(res.spins.len(), 1);
+        assert_eq!(res.spins[0].id, 2);
+        assert_eq!(res.spins[0].block_height, 12_347);
+
+        // other players' histories are separate
+        assert_eq!(history(deps.as_mut(), "someone", None, None).total, 0);
+    }
+
+    #[test]
+    fn viewing_keys() {
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        let history_query = |key: &str| QueryMsg::History { address: Addr::unchecked("alice"), key: key.to_string(), start_after: None, limit: None };
+
+        // no key set yet
+        assert!(query(deps.as_ref(), mock_env(), history_query("")).is_err());
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), ExecuteMsg::CreateViewingKey { entropy: "alice's entropy".to_string() }).unwrap();
+        let ExecuteAnswer::CreateViewingKey { key } = from_binary(&res.data.unwrap()).unwrap();
+        assert!(key.starts_with(VIEWING_KEY_PREFIX));
+
+        assert!(query(deps.as_ref(), mock_env(), history_query(&key)).is_ok());
+        assert!(query(deps.as_ref(), mock_env(), history_query("api_key_wrong")).is_err());
+
+        // bob's key doesn't open alice's history
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), ExecuteMsg::SetViewingKey { key: "bob's key".to_string() }).unwrap();
+        assert!(query(deps.as_ref(), mock_env(), history_query("bob's key")).is_err());
+
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), ExecuteMsg::SetViewingKey { key: "new key".to_string() }).unwrap();
+        assert!(query(deps.as_ref(), mock_env(), history_query(&key)).is_err());
+        assert!(query(deps.as_ref(), mock_env(), history_query("new key")).is_ok());
+    }
+
+    fn signed_permit(params: PermitParams) -> Permit {
+        use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]).unwrap();
+        let signature: Signature = signing_key.sign(&permit_sign_bytes(&params).unwrap());
+
+        Permit {
+            params,
+            signature: PermitSignature {
+                pub_key: PubKey {
+                    r#type: "tendermint/PubKeySecp256k1".to_string(),
+                    value: Binary::from(signing_key.verifying_key().to_bytes().as_slice()),
+                },
+                signature: Binary::from(signature.as_ref()),
+            },
+        }
+    }
+
+    fn permit_params(permissions: Vec<Permission>) -> PermitParams {
+        PermitParams {
+            allowed_tokens: vec![mock_env().contract.address.to_string()],
+            permit_name: "roulette".to_string(),
+            chain_id: mock_env().block.chain_id,
+            permissions,
+        }
+    }
+
+    #[test]
+    fn query_permits() {
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        let permit_query = |permit: Permit| QueryMsg::WithPermit { permit, query: QueryWithPermit::History { start_after: None, limit: None } };
+
+        let permit = signed_permit(permit_params(vec![Permission::History]));
+        let account = pubkey_to_account(permit.signature.pub_key.value.as_slice()).unwrap();
+        assert!(account.as_str().starts_with("secret1"));
+        assert_eq!(validate_permit(deps.as_ref(), &mock_env(), &permit, Permission::History).unwrap(), account);
+
+        let res: HistoryResponse = from_binary(&query(deps.as_ref(), mock_env(), permit_query(permit.clone())).unwrap()).unwrap();
+        assert_eq!(res.total, 0);
+
+        // the owner permission covers everything
+        assert!(query(deps.as_ref(), mock_env(), permit_query(signed_permit(permit_params(vec![Permission::Owner])))).is_ok());
+        assert!(query(deps.as_ref(), mock_env(), permit_query(signed_permit(permit_params(vec![Permission::PendingSpins])))).is_err());
+
+        // signed for another contract
+        let mut params = permit_params(vec![Permission::History]);
+        params.allowed_tokens = vec!["secret1othercontract".to_string()];
+        assert!(query(deps.as_ref(), mock_env(), permit_query(signed_permit(params))).is_err());
+
+        // signed for another chain
+        let mut params = permit_params(vec![Permission::History]);
+        params.chain_id = "pulsar-3".to_string();
+        let other_chain = signed_permit(params);
+        assert!(validate_permit(deps.as_ref(), &mock_env(), &other_chain, Permission::History).is_err());
+        assert!(query(deps.as_ref(), mock_env(), permit_query(other_chain)).is_err());
+
+        // params changed after signing
+        let mut tampered = permit.clone();
+        tampered.params.permissions.push(Permission::PendingSpins);
+        assert!(query(deps.as_ref(), mock_env(), permit_query(tampered)).is_err());
+
+        execute(deps.as_mut(), mock_env(), mock_info(account.as_str(), &[]), ExecuteMsg::RevokePermit { permit_name: "roulette".to_string() }).unwrap();
+        assert!(query(deps.as_ref(), mock_env(), permit_query(permit)).is_err());
+    }
+
+    #[test]
+    fn table_stats() {
+        let mut deps = mock_dependencies_with_balance(&coins(1_000_000, "token"));
+        let info = instantiate_contract(deps.as_mut());
+
+        let stats = |deps: Deps| -> StatsResponse { from_binary(&query(deps, mock_env(), QueryMsg::Stats {}).unwrap()).unwrap() };
+
+        let res = stats(deps.as_ref());
+        assert_eq!(res.total_spins, 0);
+        assert_eq!(res.pocket_counts, vec![0; 37]);
+        assert!(res.denoms.is_empty());
+
+        // the first mock roll lands on 26, a winner paying 7200 for 200
+        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Exact { num: 26 } };
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet.clone()], announced_bets: None, client_seed: None};
+        execute(deps.as_mut(), mock_env(), info.clone(), execute_msg).unwrap();
+
+        let res = stats(deps.as_ref());
+        assert_eq!(res.total_spins, 1);
+        assert_eq!(res.pocket_counts[26], 1);
+        assert_eq!(res.denoms, vec![DenomStats {
+            denom: "token".to_string(),
+            wagered: Uint128::new(200),
+            paid_out: Uint128::new(7200),
+            house_edge: Decimal::from_ratio(7000u128, 200u128),
+            players_ahead: true,
+        }]);
+
+        for _ in 0..50 {
+            let execute_msg = ExecuteMsg::Bet {bets: vec![bet.clone()], announced_bets: None, client_seed: None};
+            execute(deps.as_mut(), mock_env(), info.clone(), execute_msg).unwrap();
+        }
+
+        let res = stats(deps.as_ref());
+        assert_eq!(res.total_spins, 51);
+        assert_eq!(res.pocket_counts.iter().sum::<u64>(), 51);
+        assert_eq!(res.denoms[0].wagered, Uint128::new(200 * 51));
+        assert_eq!(res.denoms[0].paid_out, Uint128::new(7200 * res.pocket_counts[26] as u128));
+    }
+
+    fn instantiate_rounds(deps: DepsMut) -> MessageInfo {
+        let msg = InstantiateMsg { supported_denoms: Some(vec!["token".to_string()]), spin_mode: Some(SpinMode::Rounds { round_blocks: 3 }), ..Default::default() };
+        let info = mock_info("creator", &[]);
+        instantiate(deps, mock_env(), info.clone(), msg).unwrap();
+        info
+    }
+
+    fn latest_round(deps: Deps) -> Round {
+        let res = query(deps, mock_env(), QueryMsg::Round { round_id: None }).unwrap();
+        from_binary::<RoundResponse>(&res).unwrap().round.unwrap()
+    }
+
+    #[test]
+    fn round_lifecycle() {
+        let mut deps = mock_dependencies_with_balance(&coins(20_000, "token"));
+        let info = instantiate_rounds(deps.as_mut());
+        let height = mock_env().block.height;
+
+        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Exact { num: 17 } };
+        let place = ExecuteMsg::PlaceRoundBets {bets: vec![bet.clone()], announced_bets: None};
+
+        // no round yet, and single player spins are turned off
+        assert_eq!(execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(200, "token")), place.clone()).unwrap_err(), ContractError::NoOpenRound);
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet], announced_bets: None, client_seed: None};
+        assert!(matches!(execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(200, "token")), execute_msg).unwrap_err(), ContractError::WrongSpinMode { .. }));
+
+        execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::OpenRound {}).unwrap();
+        assert_eq!(execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::OpenRound {}).unwrap_err(), ContractError::RoundNotSettled { round_id: 1 });
+
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(200, "token")), place.clone()).unwrap();
+        execute(deps.as_mut(), env_at_height(height + 2), mock_info("bob", &coins(200, "token")), place.clone()).unwrap();
+
+        // betting stops on the block that can close the round
+        assert_eq!(execute(deps.as_mut(), env_at_height(height + 3), mock_info("carol", &coins(200, "token")), place).unwrap_err(), ContractError::NoOpenRound);
+        assert_eq!(execute(deps.as_mut(), env_at_height(height + 2), info.clone(), ExecuteMsg::CloseRound {}).unwrap_err(), ContractError::RoundNotClosable { height: height + 3 });
+        assert_eq!(execute(deps.as_mut(), env_at_height(height + 3), info.clone(), ExecuteMsg::SettleRound { limit: None }).unwrap_err(), ContractError::NoClosedRound);
+
+        let res = execute(deps.as_mut(), env_at_height(height + 3), mock_info("anyone", &[]), ExecuteMsg::CloseRound {}).unwrap();
+        assert_eq!(res.events[0].ty, "wasm-roulette_result");
+        assert_eq!(res.messages.last().unwrap().msg, CosmosMsg::finalize_tx());
+
+        let round = latest_round(deps.as_ref());
+        assert_eq!(round.status, RoundStatus::Closed);
+        assert_eq!(round.entries, 2);
+        assert!(round.result.is_some());
+
+        execute(deps.as_mut(), env_at_height(height + 3), mock_info("anyone", &[]), ExecuteMsg::SettleRound { limit: None }).unwrap();
+
+        let round = latest_round(deps.as_ref());
+        assert_eq!(round.status, RoundStatus::Settled);
+        assert_eq!(round.settled_entries, 2);
+        assert!(load_reserved(&deps.storage).unwrap().is_empty());
+
+        // the next round can be opened now
+        execute(deps.as_mut(), env_at_height(height + 4), info, ExecuteMsg::OpenRound {}).unwrap();
+        assert_eq!(latest_round(deps.as_ref()).id, 2);
+
+        assert_eq!(load_round(&deps.storage, 3).unwrap_err(), ContractError::RoundNotFound { round_id: 3 });
+        assert!(query(deps.as_ref(), mock_env(), QueryMsg::Round { round_id: Some(3) }).is_err());
+    }
+
+    #[test]
+    fn round_reserves_worst_pocket() {
+        let mut deps = mock_dependencies_with_balance(&coins(10_000, "token"));
+        let info = instantiate_rounds(deps.as_mut());
+
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::OpenRound {}).unwrap();
+
+        let red = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Red };
+        let black = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Black };
+
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(200, "token")), ExecuteMsg::PlaceRoundBets {bets: vec![red.clone()], announced_bets: None}).unwrap();
+        assert_eq!(load_reserved(&deps.storage).unwrap(), coins(400, "token"));
+
+        // red and black can't both win, so the round's worst case doesn't grow
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &coins(200, "token")), ExecuteMsg::PlaceRoundBets {bets: vec![black], announced_bets: None}).unwrap();
+        assert_eq!(load_reserved(&deps.storage).unwrap(), coins(400, "token"));
+
+        // a second bet from the same player goes into their entry
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(200, "token")), ExecuteMsg::PlaceRoundBets {bets: vec![red], announced_bets: None}).unwrap();
+        assert_eq!(load_reserved(&deps.storage).unwrap(), coins(800, "token"));
+
+        let round = latest_round(deps.as_ref());
+        assert_eq!(round.entries, 2);
+        assert_eq!(round.reserved, coins(800, "token"));
+        assert_eq!(load_round_entry(&deps.storage, 1, &Addr::unchecked("alice")).unwrap().unwrap().bets.len(), 2);
+    }
+
+    #[test]
+    fn round_settled_in_pages() {
+        let mut deps = mock_dependencies_with_balance(&coins(10_000, "token"));
+        let info = instantiate_rounds(deps.as_mut());
+        let height = mock_env().block.height;
+
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::OpenRound {}).unwrap();
+
+        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(10_u16) }, result: GameResult::Red };
+        for player in ["alice", "bob", "carol", "dave", "erin"] {
+            let place = ExecuteMsg::PlaceRoundBets {bets: vec![bet.clone()], announced_bets: None};
+            execute(deps.as_mut(), mock_env(), mock_info(player, &coins(10, "token")), place).unwrap();
+        }
+
+        execute(deps.as_mut(), env_at_height(height + 3), mock_info("anyone", &[]), ExecuteMsg::CloseRound {}).unwrap();
+
+        let settle = ExecuteMsg::SettleRound { limit: Some(2) };
+        for settled in [2, 4] {
+            let res = execute(deps.as_mut(), env_at_height(height + 3), mock_info("anyone", &[]), settle.clone()).unwrap();
+            assert!(res.messages.len() <= 2);
+
+            let round = latest_round(deps.as_ref());
+            assert_eq!(round.status, RoundStatus::Closed);
+            assert_eq!(round.settled_entries, settled);
+        }
+
+        execute(deps.as_mut(), env_at_height(height + 3), mock_info("anyone", &[]), settle.clone()).unwrap();
+        assert_eq!(latest_round(deps.as_ref()).status, RoundStatus::Settled);
+        assert!(load_reserved(&deps.storage).unwrap().is_empty());
+
+        assert!(execute(deps.as_mut(), env_at_height(height + 3), mock_info("anyone", &[]), settle).is_err());
+    }
+
+    #[test]
+    fn instantiate_rejects_contradictory_config() {
+        let base = InstantiateMsg { supported_denoms: Some(vec!["token".to_string()]), ..Default::default() };
+
+        let invalid = [
+            InstantiateMsg { min_bet: Some(Uint128::new(100)), max_bet: Some(Uint128::new(10)), ..base.clone() },
+            InstantiateMsg { min_bet: Some(Uint128::new(100)), max_total: Some(Uint128::new(10)), ..base.clone() },
+            InstantiateMsg { supported_denoms: Some(vec![]), ..base.clone() },
+            InstantiateMsg { supported_denoms: Some(vec!["token".to_string(), "token".to_string()]), ..base.clone() },
+            InstantiateMsg { denom_limits: Some(vec![DenomLimits { denom: "other".to_string(), min_bet: None, max_bet: None, max_total: None, categories: vec![] }]), ..base.clone() },
+            InstantiateMsg { denom_limits: Some(vec![DenomLimits { denom: "token".to_string(), min_bet: None, max_bet: None, max_total: None, categories: vec![
+                CategoryLimits { category: BetCategory::StraightUp, min_bet: Some(Uint128::new(50)), max_bet: Some(Uint128::new(5)) },
+            ] }]), ..base.clone() },
+            // category limits only tighten the denom's
+            InstantiateMsg { min_bet: Some(Uint128::new(10)), denom_limits: Some(vec![DenomLimits { denom: "token".to_string(), min_bet: None, max_bet: None, max_total: None, categories: vec![
+                CategoryLimits { category: BetCategory::Outside, min_bet: Some(Uint128::new(5)), max_bet: None },
+            ] }]), ..base.clone() },
+            InstantiateMsg { denom_limits: Some(vec![DenomLimits { denom: "token".to_string(), min_bet: None, max_bet: Some(Uint128::new(100)), max_total: None, categories: vec![
+                CategoryLimits { category: BetCategory::StraightUp, min_bet: None, max_bet: Some(Uint128::new(101)) },
+            ] }]), ..base.clone() },
+            InstantiateMsg { spin_mode: Some(SpinMode::Rounds { round_blocks: 0 }), ..base.clone() },
+        ];
+
+        for msg in invalid {
+            let mut deps = mock_dependencies();
+            let err = instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg.clone()).unwrap_err();
+            assert!(matches!(err, ContractError::InvalidConfig { .. }), "{:?}", msg);
+        }
+
+        let mut deps = mock_dependencies();
+        assert!(instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), base).is_ok());
+    }
+
+    #[test]
+    fn update_config() {
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        let config = |deps: Deps| -> ConfigResponse { from_binary(&query(deps, mock_env(), QueryMsg::Config {}).unwrap()).unwrap() };
+
+        let before = config(deps.as_ref());
+        assert_eq!(before.admin, Addr::unchecked("creator"));
+        assert_eq!(before.config.supported_denoms, vec!["token".to_string()]);
+
+        let update = ExecuteMsg::UpdateConfig(ConfigUpdate {
+            min_bet: Some(Uint128::new(10)),
+            supported_denoms: Some(vec!["token".to_string(), "uscrt".to_string()]),
+            ..Default::default()
+        });
+
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("someone", &[]), update.clone()).is_err());
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), update).unwrap();
+
+        let after = config(deps.as_ref());
+        assert_eq!(after.config.min_bet, Uint128::new(10));
+        assert_eq!(after.config.max_bet, before.config.max_bet);
+        assert_eq!(after.config.supported_denoms, vec!["token".to_string(), "uscrt".to_string()]);
+
+        // contradictory updates leave the config as it was
+        let update = ExecuteMsg::UpdateConfig(ConfigUpdate { max_bet: Some(Uint128::new(5)), ..Default::default() });
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), update).is_err());
+        assert_eq!(config(deps.as_ref()), after);
+    }
+
+    #[test]
+    fn set_payouts() {
+        let mut deps = mock_dependencies();
+
+        instantiate_contract(deps.as_mut());
+
+        let payouts = PayoutTable { straight_up: Decimal::percent(3500), ..PayoutTable::default() };
+
+        let info = mock_info("creator2", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::SetPayouts { payouts: payouts.clone() });
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::SetPayouts {
+            payouts: PayoutTable { corner: Decimal::zero(), ..PayoutTable::default() }
+        });
+        assert_eq!(res.unwrap_err(), ContractError::InvalidConfig { reason: "Payout multipliers must be greater than zero".to_string() });
+
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::SetPayouts { payouts: payouts.clone() }).unwrap();
 
-        if this_item.is_none() {
-            coins.insert(b.amount.denom.clone(), b.amount.amount);
-        } else {
-            let item = this_item.unwrap();
-            if let Ok(result) = item.checked_add(b.amount.amount) {
-                *item = result;
-            } else {
-                panic!("Overflow when adding coins");
-            }
-        }
+        assert_eq!(load_config(&deps.storage).unwrap().payouts, payouts);
     }
 
-    Ok(coins)
-}
+    #[test]
+    fn change_admin() {
+        let mut deps = mock_dependencies_with_balance(&coins(1_000, "token"));
+        let _env = mock_env();
 
-fn validate_amounts(sent_funds: &Vec<Coin>, config: Config) -> StdResult<()> {
-    let max_total = config.max_total as u128;
-    let min_bet = config.min_bet as u128;
+        instantiate_contract(deps.as_mut());
+        // test withdraw with admin
 
-    for funds in sent_funds {
-        if funds.amount.u128() > max_total {
-            return Err(StdError::generic_err("Bet is higher than table maximum"));
-        }
+        let info = mock_info("creator", &coins(200, "token"));
+        let change_admin_msg = ExecuteMsg::ProposeAdmin {admin: "creator2".to_string(), expiry_blocks: None};
+        let res = execute(deps.as_mut(), mock_env(), info, change_admin_msg);
 
-        if funds.amount.u128() < min_bet {
-            return Err(StdError::generic_err("Bet is lower than table minimum"));
-        }
-        if !config.supported_denoms.contains(&funds.denom) {
-            return Err(StdError::generic_err("Denom unsupported supported"));
-        }
-    }
+        assert!(res.is_ok());
 
-    Ok(())
-}
+        let info = mock_info("creator2", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::AcceptAdmin {});
 
-fn check_coins_match_input(coins: HashMap<String, Uint128>, sent_funds: Vec<Coin>) -> bool {
-    for funds in sent_funds {
+        assert!(res.is_ok());
 
-        if coins.get(&funds.denom).unwrap_or(&Uint128::zero()) != &funds.amount {
-            return false;
-        }
-    }
+        // test withdraw without admin
 
-    true
-}
+        let info = mock_info("creator2", &coins(1, "token"));
+        let withdraw = ExecuteMsg::AdminWithdraw {coin: Coin {denom: "token".to_string(), amount: Uint128::from(200_u16)}};
+        let res = execute(deps.as_mut(), mock_env(), info, withdraw);
 
-fn handle_game_result(deps: DepsMut, env: Env, info: MessageInfo, bets: Vec<Bet>) -> Result<Response, StdError> {
+        // make sure message failed
+        assert!(res.is_ok());
+    }
 
-    deps.api.debug(&format!("Bets are in: {:?}", bets));
+    #[test]
+    fn change_admin_fail() {
+        let mut deps = mock_dependencies();
+        let _env = mock_env();
 
-    let config = load_config(deps.storage)?;
+        instantiate_contract(deps.as_mut());
 
-    let sums = calculate_sum_coins_of_bets(&bets, &config)?;
+        // test withdraw with admin
 
-    validate_amounts(&info.funds, config)?;
+        let info = mock_info("creator2", &coins(200, "token"));
+        let change_admin_msg = ExecuteMsg::ProposeAdmin {admin: "creator2".to_string(), expiry_blocks: None};
+        let res = execute(deps.as_mut(), mock_env(), info, change_admin_msg);
 
-    if !check_coins_match_input(sums, info.funds) {
-        return Err(StdError::generic_err("Input funds don't match sum of bets"));
+        assert!(res.is_err());
     }
 
+    #[test]
+    fn admin_proposal() {
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+        let height = mock_env().block.height;
 
-    for b in &bets {
-        if !b.result.validate() {
-            deps.api.debug(&format!("Invalid bet dawg: {:?}", b.result));
-            return Err(StdError::generic_err("Error, invalid bet"));
-        }
+        let pending_admin = |deps: Deps| -> Option<PendingAdmin> {
+            from_binary::<PendingAdminResponse>(&query(deps, mock_env(), QueryMsg::PendingAdmin {}).unwrap()).unwrap().pending
+        };
+        let admin = |deps: Deps| load_admin(deps.storage).unwrap();
+
+        let propose = ExecuteMsg::ProposeAdmin {admin: "typo".to_string(), expiry_blocks: Some(10)};
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), propose).unwrap();
+        assert_eq!(pending_admin(deps.as_ref()), Some(PendingAdmin { address: Addr::unchecked("typo"), expires_at: height + 10 }));
+
+        // proposing doesn't hand over anything yet, and only the proposed address can accept
+        assert_eq!(admin(deps.as_ref()), Addr::unchecked("creator"));
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("someone", &[]), ExecuteMsg::AcceptAdmin {}).is_err());
+
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("someone", &[]), ExecuteMsg::CancelAdminProposal {}).is_err());
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), ExecuteMsg::CancelAdminProposal {}).unwrap();
+        assert_eq!(pending_admin(deps.as_ref()), None);
+        assert_eq!(execute(deps.as_mut(), mock_env(), mock_info("typo", &[]), ExecuteMsg::AcceptAdmin {}).unwrap_err(), ContractError::Unauthorized);
+
+        // proposals run out
+        let propose = ExecuteMsg::ProposeAdmin {admin: "creator2".to_string(), expiry_blocks: Some(10)};
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), propose).unwrap();
+        let res = execute(deps.as_mut(), env_at_height(height + 11), mock_info("creator2", &[]), ExecuteMsg::AcceptAdmin {});
+        assert_eq!(res.unwrap_err(), ContractError::ProposalExpired { expired_at: height + 10 });
+
+        // the roles handed out by the previous owner stay until the new owner revokes them
+        let grant = ExecuteMsg::GrantRole { role: Role::Operator, address: "croupier".to_string() };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), grant).unwrap();
+
+        execute(deps.as_mut(), env_at_height(height + 10), mock_info("creator2", &[]), ExecuteMsg::AcceptAdmin {}).unwrap();
+        assert_eq!(admin(deps.as_ref()), Addr::unchecked("creator2"));
+        assert_eq!(pending_admin(deps.as_ref()), None);
+        assert!(has_role(&deps.storage, Role::Operator, &Addr::unchecked("croupier")).unwrap());
+
+        let revoke = ExecuteMsg::RevokeRole { role: Role::Operator, address: "croupier".to_string() };
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), revoke.clone()).is_err());
+        execute(deps.as_mut(), mock_env(), mock_info("creator2", &[]), revoke).unwrap();
+        assert!(!has_role(&deps.storage, Role::Operator, &Addr::unchecked("croupier")).unwrap());
+
+        // the proposed admin has to be a valid address
+        let propose = ExecuteMsg::ProposeAdmin {admin: "Creator3".to_string(), expiry_blocks: None};
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("creator2", &[]), propose).is_err());
+        assert_eq!(pending_admin(deps.as_ref()), None);
     }
 
-    if env.block.random.is_none() {
-        return Err(StdError::generic_err("Error, random not available"));
-    }
-    let r: Binary = env.block.random.unwrap();
+    #[test]
+    fn roles() {
+        let mut deps = mock_dependencies_with_balance(&coins(1_000, "token"));
+        instantiate_contract(deps.as_mut());
 
-    let mut prng = Prng::new(r.as_slice());
+        let owner = mock_info("creator", &[]);
+        let grant = |role: Role, address: &str| ExecuteMsg::GrantRole { role, address: address.to_string() };
+        let withdraw = ExecuteMsg::AdminWithdraw {coin: Coin::new(100, "token")};
+        let update = ExecuteMsg::UpdateConfig(ConfigUpdate { min_bet: Some(Uint128::new(1)), ..Default::default() });
+
+        // only the owner hands out roles, and the owner role isn't one of them
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("teller", &[]), grant(Role::Treasurer, "teller")).is_err());
+        assert_eq!(execute(deps.as_mut(), mock_env(), owner.clone(), grant(Role::Owner, "teller")).unwrap_err(), ContractError::OwnerNotGrantable);
+
+        // roles only go to valid addresses
+        let not_normalized = ContractError::Std(StdError::generic_err("Invalid input: address not normalized"));
+        assert_eq!(execute(deps.as_mut(), mock_env(), owner.clone(), grant(Role::Treasurer, "Teller")).unwrap_err(), not_normalized);
+        let revoke = ExecuteMsg::RevokeRole { role: Role::Treasurer, address: "Teller".to_string() };
+        assert_eq!(execute(deps.as_mut(), mock_env(), owner.clone(), revoke).unwrap_err(), not_normalized);
+
+        execute(deps.as_mut(), mock_env(), owner.clone(), grant(Role::Treasurer, "teller")).unwrap();
+        execute(deps.as_mut(), mock_env(), owner.clone(), grant(Role::Operator, "croupier")).unwrap();
+        execute(deps.as_mut(), mock_env(), owner.clone(), grant(Role::Pauser, "guard")).unwrap();
+
+        let res: RolesResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Roles {}).unwrap()).unwrap();
+        assert_eq!(res, RolesResponse {
+            owner: Addr::unchecked("creator"),
+            treasurers: vec![Addr::unchecked("teller")],
+            operators: vec![Addr::unchecked("croupier")],
+            pausers: vec![Addr::unchecked("guard")],
+        });
+
+        // every role does its own job and nothing else
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("teller", &[]), withdraw.clone()).is_ok());
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("teller", &[]), update.clone()).is_err());
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("croupier", &[]), update.clone()).is_ok());
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("croupier", &[]), withdraw.clone()).is_err());
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("guard", &[]), withdraw.clone()).is_err());
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("guard", &[]), grant(Role::Treasurer, "guard")).is_err());
+
+        // the payouts, safeguards and payout mode stay with the owner, even next to a limit change
+        let circuit_breaker = CircuitBreaker { max_net_loss: coins(100, "token"), window_blocks: 10 };
+        let privileged = [
+            ExecuteMsg::SetPayouts { payouts: PayoutTable::default() },
+            ExecuteMsg::UpdateConfig(ConfigUpdate { circuit_breaker: Some(circuit_breaker), ..Default::default() }),
+            ExecuteMsg::UpdateConfig(ConfigUpdate { remove_circuit_breaker: Some(true), ..Default::default() }),
+            ExecuteMsg::UpdateConfig(ConfigUpdate { contract_callers: Some(ContractCallers::AllowListed { contracts: vec![] }), ..Default::default() }),
+            ExecuteMsg::UpdateConfig(ConfigUpdate { min_bet: Some(Uint128::new(1)), payout_mode: Some(PayoutMode::Claim), ..Default::default() }),
+        ];
+        for msg in privileged.clone() {
+            assert_eq!(execute(deps.as_mut(), mock_env(), mock_info("croupier", &[]), msg).unwrap_err(), ContractError::Unauthorized);
+        }
+        assert_eq!(load_config(&deps.storage).unwrap().payout_mode, PayoutMode::Push);
 
-    // this is probably fine since the modulo bias is super small
-    let result = prng.next_u32() % 37;
+        // the owner can do everything
+        for msg in privileged {
+            assert!(execute(deps.as_mut(), mock_env(), owner.clone(), msg).is_ok());
+        }
+        assert!(execute(deps.as_mut(), mock_env(), owner.clone(), withdraw.clone()).is_ok());
+        assert!(execute(deps.as_mut(), mock_env(), owner.clone(), update).is_ok());
 
-    deps.api.debug(&format!("Roll result: {:?}", result));
+        execute(deps.as_mut(), mock_env(), owner, ExecuteMsg::RevokeRole { role: Role::Treasurer, address: "teller".to_string() }).unwrap();
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("teller", &[]), withdraw).is_err());
+    }
 
-    let winners = return_winning_numbers(result);
+    #[test]
+    fn contract_status() {
+        let mut deps = mock_dependencies_with_balance(&coins(10_000, "token"));
+        let info = instantiate_delayed(deps.as_mut());
+        let height = mock_env().block.height;
 
-    deps.api.debug(&format!("Winning bets are: {:?}", winners));
+        let status = |deps: Deps| from_binary::<StatusResponse>(&query(deps, mock_env(), QueryMsg::Status {}).unwrap()).unwrap().status;
+        let set_status = |status: ContractStatus| ExecuteMsg::SetStatus { status };
 
-    let mut winning_bets = vec![];
-    for bet in bets {
-        if winners.contains(&bet.result) {
-            winning_bets.push(bet)
-        }
-    }
+        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(100_u16) }, result: GameResult::Red };
+        let place = ExecuteMsg::PlaceBets {bets: vec![bet], announced_bets: None};
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &coins(100, "token")), place.clone()).unwrap();
 
-    let mut payouts: HashMap<String, Uint128> = std::collections::HashMap::new();
+        execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::GrantRole { role: Role::Pauser, address: "guard".to_string() }).unwrap();
 
-    let mut winning_bets_evt = Event::new("winners");
+        // pausers can only halt betting
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("someone", &[]), set_status(ContractStatus::BettingPaused)).is_err());
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("guard", &[]), set_status(ContractStatus::FullyStopped)).is_err());
+        execute(deps.as_mut(), mock_env(), mock_info("guard", &[]), set_status(ContractStatus::BettingPaused)).unwrap();
+        assert_eq!(status(deps.as_ref()), ContractStatus::BettingPaused);
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("guard", &[]), set_status(ContractStatus::Running)).is_err());
 
-    for win_bet in winning_bets {
-        let payout_amount = win_bet.amount.amount * Uint128::from(win_bet.result.payout());
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("creator", &coins(100, "token")), place.clone()).is_err());
 
-        winning_bets_evt = winning_bets_evt.add_attribute_plaintext(win_bet.result.clone(), payout_amount);
+        // a full stop holds settlement too, admin messages keep working
+        execute(deps.as_mut(), mock_env(), info.clone(), set_status(ContractStatus::FullyStopped)).unwrap();
+        assert!(execute(deps.as_mut(), env_at_height(height + 2), mock_info("anyone", &[]), ExecuteMsg::Settle { spin_id: 1 }).is_err());
 
-       // winning_bets_attrs.push(Attribute::new(win_bet.result.clone(), payout_amount));
+        // only the owner lifts a full stop, even down to a pause
+        let res = execute(deps.as_mut(), mock_env(), mock_info("guard", &[]), set_status(ContractStatus::BettingPaused));
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+        assert_eq!(status(deps.as_ref()), ContractStatus::FullyStopped);
+        assert!(execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::AdminWithdraw {coin: Coin::new(100, "token")}).is_ok());
 
-        payouts.entry(win_bet.amount.denom).and_modify(|amount| *amount += payout_amount).or_insert(payout_amount);
+        execute(deps.as_mut(), mock_env(), info.clone(), set_status(ContractStatus::BettingPaused)).unwrap();
+        assert!(execute(deps.as_mut(), env_at_height(height + 2), mock_info("anyone", &[]), ExecuteMsg::Settle { spin_id: 1 }).is_ok());
 
+        execute(deps.as_mut(), mock_env(), info, set_status(ContractStatus::Running)).unwrap();
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("creator", &coins(100, "token")), place).is_ok());
     }
 
-    let coins_to_send: Vec<Coin> = payouts.iter().map(|payout| Coin { denom: payout.0.to_string(), amount: *payout.1 }).collect();
+    #[test]
+    fn circuit_breaker_pauses_betting() {
+        let mut deps = mock_dependencies_with_balance(&coins(1_000_000, "token"));
 
-    let resp = Response::new().add_event(Event::new("wasm-roulette_result").add_attribute_plaintext(
-        "result", result.to_string()
-    ));
+        let circuit_breaker = CircuitBreaker { window_blocks: 100, max_net_loss: coins(5_000, "token") };
+        let msg = InstantiateMsg { supported_denoms: Some(vec!["token".to_string()]), circuit_breaker: Some(circuit_breaker), ..Default::default() };
+        let info = mock_info("creator", &coins(200, "token"));
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
-    if !coins_to_send.is_empty() {
-        deps.api.debug(&format!("payouts to send: {:?}", coins_to_send));
+        // the first mock roll lands on 26, the house is down 7000
+        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Exact { num: 26 } };
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet], announced_bets: None, client_seed: None};
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), execute_msg.clone()).unwrap();
 
-        let msg = BankMsg::Send { to_address: info.sender.to_string(), amount: coins_to_send };
+        assert!(res.events.iter().any(|e| e.ty == "wasm-roulette_circuit_breaker"));
+        assert_eq!(load_status(&deps.storage).unwrap(), ContractStatus::BettingPaused);
+        assert!(execute(deps.as_mut(), mock_env(), info, execute_msg).is_err());
+    }
 
-        Ok(resp
-            .add_event(winning_bets_evt)
-            .add_message(msg)
-            .add_message(
-                CosmosMsg::finalize_tx()
-            )
+    #[test]
+    fn loss_window_restarts() {
+        let mut deps = mock_dependencies();
 
-        )
-    } else {
-        Ok(resp)
+        let window = record_loss_window(&mut deps.storage, 100, 10, &coins(10, "token"), &coins(50, "token")).unwrap();
+        assert_eq!(window.started_at, 100);
+
+        let window = record_loss_window(&mut deps.storage, 109, 10, &coins(10, "token"), &[]).unwrap();
+        assert_eq!(window.started_at, 100);
+        assert_eq!(window.wagered, coins(20, "token"));
+        assert_eq!(window.paid_out, coins(50, "token"));
+
+        let window = record_loss_window(&mut deps.storage, 110, 10, &coins(10, "token"), &[]).unwrap();
+        assert_eq!(window.started_at, 110);
+        assert_eq!(window.wagered, coins(10, "token"));
+        assert!(window.paid_out.is_empty());
     }
-}
 
+    #[test]
+    fn empty_and_zero_bets() {
+        let mut deps = mock_dependencies_with_balance(&coins(1_000_000, "token"));
+        let info = mock_info("creator", &[]);
+        instantiate_contract(deps.as_mut());
 
-#[entry_point]
-pub fn query(_deps: Deps, _env: Env, _msg: QueryMsg) -> StdResult<Binary> {
-    Ok(Binary::default())
-}
+        let execute_msg = ExecuteMsg::Bet {bets: vec![], announced_bets: None, client_seed: None};
+        assert_eq!(execute(deps.as_mut(), mock_env(), info.clone(), execute_msg).unwrap_err(), ContractError::EmptySlip);
 
-#[cfg(test)]
-mod tests {
-    
-    use super::*;
+        let bet = Bet{ amount: Coin::new(0, "token"), result: GameResult::Red };
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet], announced_bets: None, client_seed: None};
+        assert_eq!(execute(deps.as_mut(), mock_env(), info.clone(), execute_msg).unwrap_err(), ContractError::ZeroBet { bet: "red".to_string() });
 
-    use cosmwasm_std::testing::{
-        mock_dependencies, mock_env, mock_info,
-    };
-    use cosmwasm_std::{Addr, coins};
-    use std::collections::HashMap;
-    use crate::contract::return_winning_numbers;
-    /// Just set sender and funds for the message.
-    /// This is intended for use in test code only.
+        // a zero bet can't ride along with real ones either
+        let bets = vec![
+            Bet{ amount: Coin::new(100, "token"), result: GameResult::Black },
+            Bet{ amount: Coin::new(0, "token"), result: GameResult::Red },
+        ];
+        let execute_msg = ExecuteMsg::Bet {bets, announced_bets: None, client_seed: None};
+        let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &coins(100, "token")), execute_msg);
+        assert_eq!(res.unwrap_err(), ContractError::ZeroBet { bet: "red".to_string() });
 
-    fn instantiate_contract(deps: DepsMut) -> MessageInfo {
-        let msg = InstantiateMsg { min_bet: None, max_bet: None, max_total: None, supported_denoms: Some(vec!["token".to_string()]), admin: None };
-        let info = mock_info("creator", &coins(200, "token"));
-        let _res = instantiate(deps, mock_env(), info.clone(), msg).unwrap();
-        info
+        // the table has no minimum bet, and still nothing was rolled
+        assert_eq!(load_config(&deps.storage).unwrap().min_bet, Uint128::zero());
+        assert_eq!(history(deps.as_mut(), "creator", None, None).total, 0);
     }
 
     #[test]
-    fn new_game_loser() {
-        let mut deps = mock_dependencies();
-        let _env = mock_env();
+    fn contract_callers_policy() {
+        let mut deps = mock_dependencies_with_balance(&coins(1_000_000, "token"));
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::ContractInfo { contract_addr } if contract_addr == "bot" || contract_addr == "partner" =>
+                SystemResult::Ok(ContractResult::Ok(to_binary(&ContractInfoResponse::new(1, "deployer")).unwrap())),
+            _ => SystemResult::Err(SystemError::NoSuchContract { addr: "".to_string() }),
+        });
 
         let info = instantiate_contract(deps.as_mut());
 
-        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Black };
+        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Red };
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet], announced_bets: None, client_seed: None};
 
-        let execute_msg = ExecuteMsg::Bet {bets: vec![bet]};
+        // contracts can bet until the table restricts them
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("bot", &coins(200, "token")), execute_msg.clone()).is_ok());
 
-        let res = execute(deps.as_mut(), mock_env(), info, execute_msg).unwrap();
+        let contract_callers = ContractCallers::AllowListed { contracts: vec![Addr::unchecked("partner")] };
+        let update = ExecuteMsg::UpdateConfig(ConfigUpdate { contract_callers: Some(contract_callers), ..Default::default() });
+        execute(deps.as_mut(), mock_env(), info.clone(), update).unwrap();
+
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("bot", &coins(200, "token")), execute_msg.clone()).is_err());
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("partner", &coins(200, "token")), execute_msg.clone()).is_ok());
+        assert!(execute(deps.as_mut(), mock_env(), info.clone(), execute_msg.clone()).is_ok());
 
-        assert_eq!(res.messages.len(), 0)
+        // an empty list keeps every contract out
+        let update = ExecuteMsg::UpdateConfig(ConfigUpdate { contract_callers: Some(ContractCallers::AllowListed { contracts: vec![] }), ..Default::default() });
+        execute(deps.as_mut(), mock_env(), info.clone(), update).unwrap();
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("partner", &coins(200, "token")), execute_msg.clone()).is_err());
+        assert!(execute(deps.as_mut(), mock_env(), info.clone(), execute_msg.clone()).is_ok());
+
+        // a lookup that fails for any other reason doesn't let the sender through
+        deps.querier.update_wasm(|_| SystemResult::Err(SystemError::Unknown {}));
+        let res = execute(deps.as_mut(), mock_env(), info, execute_msg);
+        assert_eq!(res.unwrap_err(), ContractError::CallerUnverified { address: "creator".to_string() });
     }
 
     #[test]
-    fn new_game_winner() {
+    fn migrate_versions() {
         let mut deps = mock_dependencies();
-        let _env = mock_env();
+        instantiate_contract(deps.as_mut());
 
-        let info = instantiate_contract(deps.as_mut());
+        let stored = load_contract_version(&deps.storage).unwrap().unwrap();
+        assert_eq!(stored, ContractVersion { contract: CONTRACT_NAME.to_string(), version: CONTRACT_VERSION.to_string() });
 
-        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Line {nums: (1, 2)} };
+        // migrating to the same code can still change the config
+        let update = ConfigUpdate { max_bet: Some(Uint128::new(1_000)), ..ConfigUpdate::default() };
+        migrate(deps.as_mut(), mock_env(), MigrateMsg { config: Some(update) }).unwrap();
+        assert_eq!(load_config(&deps.storage).unwrap().max_bet, Uint128::new(1_000));
 
-        let execute_msg = ExecuteMsg::Bet {bets: vec![bet]};
+        let invalid = ConfigUpdate { supported_denoms: Some(vec![]), ..ConfigUpdate::default() };
+        assert!(migrate(deps.as_mut(), mock_env(), MigrateMsg { config: Some(invalid) }).is_err());
 
-        let res = execute(deps.as_mut(), mock_env(), info, execute_msg).unwrap();
+        save_contract_version(&mut deps.storage, &ContractVersion { contract: CONTRACT_NAME.to_string(), version: "99.0.0".to_string() }).unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg { config: None }).unwrap_err();
+        assert_eq!(err, ContractError::Downgrade { from: "99.0.0".to_string(), to: CONTRACT_VERSION.to_string() });
 
-        // bank send + last message marker
-        assert_eq!(res.messages.len(), 2)
+        save_contract_version(&mut deps.storage, &ContractVersion { contract: "another_contract".to_string(), version: "0.1.0".to_string() }).unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg { config: None }).unwrap_err();
+        assert_eq!(err, ContractError::WrongContract { contract: "another_contract".to_string() });
     }
 
     #[test]
-    fn change_admin() {
-        let mut deps = mock_dependencies();
-        let _env = mock_env();
+    fn migrate_legacy_table() {
+        let mut deps = mock_dependencies_with_balance(&coins(1_000_000, "token"));
+
+        // a table instantiated by 0.1.0, which stored no version
+        let legacy = LegacyConfig { min_bet: 10, max_bet: 5_000, max_total: u64::MAX, supported_denoms: vec!["token".to_string()] };
+        LEGACY_CONFIG_ITEM.save(&mut deps.storage, &legacy).unwrap();
+        save_admin(&mut deps.storage, &Addr::unchecked("creator")).unwrap();
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { config: None }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "previous_version" && a.value == "0.1.0"));
+
+        let config = load_config(&deps.storage).unwrap();
+        assert_eq!(config.min_bet, Uint128::new(10));
+        assert_eq!(config.max_bet, Uint128::new(5_000));
+        assert_eq!(config.max_total, Uint128::MAX);
+        assert_eq!(config.wheel, WheelVariant::SingleZero);
+        assert_eq!(load_contract_version(&deps.storage).unwrap().unwrap().version, CONTRACT_VERSION);
+
+        // the table plays on with the migrated config
+        let bet = Bet{ amount: Coin::new(200, "token"), result: GameResult::Red };
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet], announced_bets: None, client_seed: None};
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("creator", &coins(200, "token")), execute_msg).is_ok());
+    }
 
-        instantiate_contract(deps.as_mut());
-        // test withdraw with admin
+    #[test]
+    fn parse_versions() {
+        assert_eq!("0.2.0".parse::<Version>().unwrap(), Version(0, 2, 0));
+        assert!(Version(0, 10, 0) > Version(0, 9, 3));
+        assert!("0.2".parse::<Version>().is_err());
+        assert!("0.2.x".parse::<Version>().is_err());
+    }
 
-        let info = mock_info("creator", &coins(200, "token"));
-        let change_admin_msg = ExecuteMsg::ChangeAdmin {admin: Addr::unchecked("creator2")};
-        let res = execute(deps.as_mut(), mock_env(), info, change_admin_msg);
+    #[test]
+    fn claim_mode_credits_winnings() {
+        let mut deps = mock_dependencies_with_balance(&coins(1_000_000, "token"));
 
-        assert!(res.is_ok());
+        let msg = InstantiateMsg { supported_denoms: Some(vec!["token".to_string()]), payout_mode: Some(PayoutMode::Claim), ..Default::default() };
+        let info = mock_info("creator", &coins(200, "token"));
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        set_viewing_key(deps.as_mut(), "creator");
 
-        // test withdraw without admin
+        let balance = |deps: Deps| -> Vec<Coin> {
+            let query_msg = QueryMsg::Balance { address: Addr::unchecked("creator"), key: "key".to_string() };
+            from_binary::<BalanceResponse>(&query(deps, mock_env(), query_msg).unwrap()).unwrap().balance
+        };
 
-        let info = mock_info("creator2", &coins(1, "token"));
-        let withdraw = ExecuteMsg::AdminWithdraw {coin: Coin {denom: "token".to_string(), amount: Uint128::from(200_u16)}};
-        let res = execute(deps.as_mut(), mock_env(), info, withdraw);
+        // the winning split is credited instead of sent
+        let bet = Bet{ amount: Coin::new(200, "token"), result: GameResult::Line {nums: (26, 29)} };
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet], announced_bets: None, client_seed: None};
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), execute_msg).unwrap();
 
-        // make sure message failed
-        assert!(res.is_ok());
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, CosmosMsg::finalize_tx());
+        assert_eq!(balance(deps.as_ref()), coins(3600, "token"));
+
+        // credited winnings aren't part of the bankroll
+        let withdraw = |amount: u128| ExecuteMsg::AdminWithdraw { coin: Coin::new(amount, "token") };
+        assert!(execute(deps.as_mut(), mock_env(), info.clone(), withdraw(996_401)).is_err());
+        assert!(execute(deps.as_mut(), mock_env(), info.clone(), withdraw(996_400)).is_ok());
+
+        let claim = |denoms: Option<Vec<String>>| ExecuteMsg::Claim { denoms };
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), claim(Some(vec!["uscrt".to_string()]))).is_err());
+        assert_eq!(execute(deps.as_mut(), mock_env(), mock_info("someone", &[]), claim(None)).unwrap_err(), ContractError::NothingToClaim);
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), claim(None)).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: "creator".to_string(),
+            amount: coins(3600, "token"),
+        }));
+
+        assert!(balance(deps.as_ref()).is_empty());
+        assert!(load_reserved(&deps.storage).unwrap().is_empty());
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), claim(None)).is_err());
     }
 
     #[test]
-    fn change_admin_fail() {
-        let mut deps = mock_dependencies();
-        let _env = mock_env();
+    fn bets_paid_from_balance() {
+        let mut deps = mock_dependencies_with_balance(&coins(1_000_000, "token"));
 
-        instantiate_contract(deps.as_mut());
+        let msg = InstantiateMsg { supported_denoms: Some(vec!["token".to_string()]), payout_mode: Some(PayoutMode::Claim), ..Default::default() };
+        let info = mock_info("creator", &coins(200, "token"));
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let bet = Bet{ amount: Coin::new(200, "token"), result: GameResult::Line {nums: (26, 29)} };
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet], announced_bets: None, client_seed: None};
+        execute(deps.as_mut(), mock_env(), info, execute_msg).unwrap();
+
+        let balance = |deps: Deps| load_balance(deps.storage, &Addr::unchecked("creator")).unwrap();
+        assert_eq!(balance(deps.as_ref()), coins(3600, "token"));
+
+        // more than the balance and the funds sent together can't be staked
+        let slip = |amount: u128| ExecuteMsg::Bet {
+            bets: vec![
+                Bet { amount: Coin::new(amount, "token"), result: GameResult::Red },
+                Bet { amount: Coin::new(amount, "token"), result: GameResult::Black },
+            ],
+            announced_bets: None,
+            client_seed: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &coins(500, "token")), slip(2_051)).unwrap_err();
+        assert_eq!(err, ContractError::BalanceTooLow { denom: "token".to_string(), expected: Uint128::new(4_102), sent: Uint128::new(500), balance: Uint128::new(3600) });
+        assert_eq!(execute(deps.as_mut(), mock_env(), mock_info("someone", &[]), slip(1_000)).unwrap_err(),
+            ContractError::BalanceTooLow { denom: "token".to_string(), expected: Uint128::new(2_000), sent: Uint128::zero(), balance: Uint128::zero() });
+        assert_eq!(balance(deps.as_ref()), coins(3600, "token"));
+
+        // 500 is sent and the other 1500 comes out of the balance
+        // either colour pays twice its stake unless the ball lands on zero
+        let won = |res: &Response, stake: u128| {
+            let event = res.events.iter().find(|e| e.ty == "wasm-roulette_result").unwrap();
+            let result = &event.attributes.iter().find(|a| a.key == "result").unwrap().value;
+            if result == "0" { 0 } else { 2 * stake }
+        };
 
-        // test withdraw with admin
+        let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &coins(500, "token")), slip(1_000)).unwrap();
+        let expected = 2_100 + won(&res, 1_000);
 
-        let info = mock_info("creator2", &coins(200, "token"));
-        let change_admin_msg = ExecuteMsg::ChangeAdmin {admin: Addr::unchecked("creator2")};
-        let res = execute(deps.as_mut(), mock_env(), info, change_admin_msg);
+        assert_eq!(balance(deps.as_ref()), coins(expected, "token"));
+        // only the balance left is reserved, the stake drawn from it backed the spin
+        assert_eq!(load_reserved(&deps.storage).unwrap(), coins(expected, "token"));
 
-        assert!(res.is_err());
+        // a slip can be paid from the balance alone
+        let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), slip(5)).unwrap();
+        let expected = expected - 10 + won(&res, 5);
+
+        assert_eq!(balance(deps.as_ref()), coins(expected, "token"));
+        assert_eq!(load_reserved(&deps.storage).unwrap(), coins(expected, "token"));
     }
 
     #[test]
     fn admin_withdraw() {
-        let mut deps = mock_dependencies();
+        let mut deps = mock_dependencies_with_balance(&coins(1_000_000, "token"));
         let _env = mock_env();
 
         let info = instantiate_contract(deps.as_mut());
 
-        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Line {nums: (1, 2)} };
+        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Line {nums: (26, 29)} };
 
-        let execute_msg = ExecuteMsg::Bet {bets: vec![bet]};
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet], announced_bets: None, client_seed: None};
 
         let res = execute(deps.as_mut(), mock_env(), info.clone(), execute_msg).unwrap();
 
@@ -496,6 +2786,12 @@ mod tests {
 
         test_cases.insert(0, vec![
             GameResult::Exact { num: 0 },
+            GameResult::Line { nums: (0, 1) },
+            GameResult::Line { nums: (0, 2) },
+            GameResult::Line { nums: (0, 3) },
+            GameResult::Trio { nums: (0, 1, 2) },
+            GameResult::Trio { nums: (0, 2, 3) },
+            GameResult::Basket,
         ]);
 
         for i in 1..=36 {
@@ -564,6 +2860,41 @@ mod tests {
                 v
             };
 
+            let streets: Vec<GameResult> = {
+                let first = (i - 1) / 3 * 3 + 1;
+                let mut v = vec![GameResult::Street { nums: (first, first + 1, first + 2) }];
+
+                if first > 1 {
+                    v.push(GameResult::SixLine {
+                        nums: (first - 3, first - 2, first - 1, first, first + 1, first + 2),
+                    });
+                }
+                if first < 34 {
+                    v.push(GameResult::SixLine {
+                        nums: (first, first + 1, first + 2, first + 3, first + 4, first + 5),
+                    });
+                }
+
+                v
+            };
+
+            let zero_bets: Vec<GameResult> = {
+                let mut v = Vec::new();
+
+                if i <= 3 {
+                    v.push(GameResult::Line { nums: (0, i) });
+                    v.push(GameResult::Basket);
+                }
+                if i <= 2 {
+                    v.push(GameResult::Trio { nums: (0, 1, 2) });
+                }
+                if i == 2 || i == 3 {
+                    v.push(GameResult::Trio { nums: (0, 2, 3) });
+                }
+
+                v
+            };
+
             let mut outcomes = vec![
                 GameResult::Exact { num: i },
                 range,
@@ -574,6 +2905,8 @@ mod tests {
 
             outcomes.extend(lines);
             outcomes.extend(corners);
+            outcomes.extend(streets);
+            outcomes.extend(zero_bets);
 
             if i <= 18 {
                 outcomes.push(GameResult::Range1to18);
@@ -605,7 +2938,7 @@ mod tests {
         // Iterate through test_cases and check if return_winning_numbers
         // provides the correct results
         for (roll_result, expected_outcomes) in test_cases.iter() {
-            let winning_numbers = return_winning_numbers(*roll_result);
+            let winning_numbers = return_winning_numbers(*roll_result, WheelVariant::SingleZero).unwrap();
             if winning_numbers != *expected_outcomes {
 
                 let differences = winning_numbers.iter().filter(
@@ -633,10 +2966,164 @@ mod tests {
         }
     }
 
-    use cosmwasm_std::{Uint128};
+    #[test]
+    fn test_return_winning_numbers_double_zero() {
+        let wheel = WheelVariant::DoubleZero;
+
+        assert_eq!(return_winning_numbers(DOUBLE_ZERO, wheel).unwrap(), vec![
+            GameResult::Exact { num: DOUBLE_ZERO },
+            GameResult::TopLine,
+            GameResult::Line { nums: (DOUBLE_ZERO, 2) },
+            GameResult::Line { nums: (DOUBLE_ZERO, 3) },
+            GameResult::Line { nums: (0, DOUBLE_ZERO) },
+            GameResult::Trio { nums: (0, DOUBLE_ZERO, 2) },
+            GameResult::Trio { nums: (DOUBLE_ZERO, 2, 3) },
+        ]);
+        assert_eq!(return_winning_numbers(0, wheel).unwrap(), vec![
+            GameResult::Exact { num: 0 },
+            GameResult::TopLine,
+            GameResult::Line { nums: (0, 1) },
+            GameResult::Line { nums: (0, 2) },
+            GameResult::Line { nums: (0, DOUBLE_ZERO) },
+            GameResult::Trio { nums: (0, 1, 2) },
+            GameResult::Trio { nums: (0, DOUBLE_ZERO, 2) },
+        ]);
+
+        // top line covers the first street as well, past that the numbers are unchanged
+        for i in 1..=36 {
+            let single = return_winning_numbers(i, WheelVariant::SingleZero).unwrap();
+            let double = return_winning_numbers(i, wheel).unwrap();
+
+            assert_eq!(double.contains(&GameResult::TopLine), i <= 3);
+            if i > 3 {
+                assert_eq!(single, double);
+            }
+        }
+    }
+
+    #[test]
+    fn test_return_winning_numbers_triple_zero() {
+        let wheel = WheelVariant::TripleZero;
+
+        for num in [0, DOUBLE_ZERO, TRIPLE_ZERO] {
+            let winners = return_winning_numbers(num, wheel).unwrap();
+
+            assert_eq!(winners[0], GameResult::Exact { num });
+            assert!(winners.contains(&GameResult::Trio { nums: (0, DOUBLE_ZERO, TRIPLE_ZERO) }));
+            assert!(!winners.contains(&GameResult::Basket));
+            assert!(!winners.contains(&GameResult::TopLine));
+        }
+    }
+
+    #[test]
+    fn test_validate_wheel_variant() {
+        assert!(GameResult::Exact { num: DOUBLE_ZERO }.validate(WheelVariant::SingleZero).is_err());
+        assert!(GameResult::Exact { num: DOUBLE_ZERO }.validate(WheelVariant::DoubleZero).is_ok());
+        assert!(GameResult::Exact { num: TRIPLE_ZERO }.validate(WheelVariant::DoubleZero).is_err());
+        assert!(GameResult::Exact { num: TRIPLE_ZERO }.validate(WheelVariant::TripleZero).is_ok());
+
+        assert!(GameResult::TopLine.validate(WheelVariant::SingleZero).is_err());
+        assert!(GameResult::TopLine.validate(WheelVariant::DoubleZero).is_ok());
+        assert!(GameResult::TopLine.validate(WheelVariant::TripleZero).is_err());
+    }
+
+    #[test]
+    fn test_validate_inside_bets() {
+        let wheel = WheelVariant::SingleZero;
+
+        assert!(GameResult::Street { nums: (4, 5, 6) }.validate(wheel).is_ok());
+        assert!(GameResult::Street { nums: (36, 34, 35) }.validate(wheel).is_ok());
+        assert!(GameResult::Street { nums: (5, 6, 7) }.validate(wheel).is_err());
+        assert!(GameResult::Street { nums: (37, 38, 39) }.validate(wheel).is_err());
+
+        assert!(GameResult::SixLine { nums: (31, 32, 33, 34, 35, 36) }.validate(wheel).is_ok());
+        assert!(GameResult::SixLine { nums: (34, 35, 36, 37, 38, 39) }.validate(wheel).is_err());
+        assert!(GameResult::SixLine { nums: (2, 3, 4, 5, 6, 7) }.validate(wheel).is_err());
+
+        assert!(GameResult::Trio { nums: (2, 0, 3) }.validate(wheel).is_ok());
+        assert!(GameResult::Trio { nums: (0, 1, 3) }.validate(wheel).is_err());
+        assert!(GameResult::Trio { nums: (0, DOUBLE_ZERO, 2) }.validate(wheel).is_err());
+        assert!(GameResult::Trio { nums: (0, DOUBLE_ZERO, 2) }.validate(WheelVariant::DoubleZero).is_ok());
+
+        assert!(GameResult::Basket.validate(wheel).is_ok());
+        assert!(GameResult::Basket.validate(WheelVariant::DoubleZero).is_err());
+
+        assert!(GameResult::Line { nums: (3, 0) }.validate(wheel).is_ok());
+        assert!(GameResult::Line { nums: (0, 4) }.validate(wheel).is_err());
+        assert!(GameResult::Line { nums: (0, 3) }.validate(WheelVariant::DoubleZero).is_err());
+        assert!(GameResult::Line { nums: (DOUBLE_ZERO, 0) }.validate(WheelVariant::DoubleZero).is_ok());
+    }
+
+    #[test]
+    fn test_validate_line_and_corner_geometry() {
+        let wheel = WheelVariant::SingleZero;
+
+        assert!(GameResult::Line { nums: (1, 2) }.validate(wheel).is_ok());
+        assert!(GameResult::Line { nums: (4, 1) }.validate(wheel).is_ok());
+        assert!(GameResult::Line { nums: (35, 36) }.validate(wheel).is_ok());
+        assert!(GameResult::Line { nums: (2, 2) }.validate(wheel).is_err());
+        assert!(GameResult::Line { nums: (1, 3) }.validate(wheel).is_err());
+        assert!(GameResult::Line { nums: (1, 5) }.validate(wheel).is_err());
+        assert!(GameResult::Line { nums: (36, 37) }.validate(wheel).is_err());
+        assert_eq!(
+            GameResult::Line { nums: (3, 4) }.validate(wheel),
+            Err("3 and 4 are on different rows of the layout".to_string())
+        );
+
+        assert!(GameResult::Corner { nums: (1, 2, 4, 5) }.validate(wheel).is_ok());
+        assert!(GameResult::Corner { nums: (36, 32, 33, 35) }.validate(wheel).is_ok());
+        assert!(GameResult::Corner { nums: (1, 1, 4, 5) }.validate(wheel).is_err());
+        assert!(GameResult::Corner { nums: (1, 2, 3, 4) }.validate(wheel).is_err());
+        assert!(GameResult::Corner { nums: (34, 35, 37, 38) }.validate(wheel).is_err());
+        assert_eq!(
+            GameResult::Corner { nums: (1, 17, 30, 5) }.validate(wheel),
+            Err("1,5,17,30 are not four numbers meeting at a corner".to_string())
+        );
+        assert_eq!(
+            GameResult::Corner { nums: (3, 4, 6, 7) }.validate(wheel),
+            Err("corner 3,4,6,7 wraps around the edge of the layout".to_string())
+        );
+
+        // every corner and split the contract can pay out on should be accepted
+        for i in 1..=36 {
+            for g in return_winning_numbers(i, wheel).unwrap() {
+                assert!(g.validate(wheel).is_ok(), "{:?} rejected", g);
+            }
+        }
+    }
+
+    #[test]
+    fn new_game_invalid_corner() {
+        let mut deps = mock_dependencies();
+
+        let info = instantiate_contract(deps.as_mut());
+
+        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Corner {nums: (1, 17, 30, 5)} };
+
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet], announced_bets: None, client_seed: None};
+
+        let err = execute(deps.as_mut(), mock_env(), info, execute_msg).unwrap_err();
+
+        assert_eq!(err, ContractError::InvalidBet { bet: "quad-1,17,30,5".to_string(), reason: "1,5,17,30 are not four numbers meeting at a corner".to_string() });
+    }
+
+    #[test]
+    fn new_game_double_zero() {
+        let mut deps = mock_dependencies_with_balance(&coins(1_000_000, "token"));
+
+        let msg = InstantiateMsg { supported_denoms: Some(vec!["token".to_string()]), wheel: Some(WheelVariant::DoubleZero), ..Default::default() };
+        let info = mock_info("creator", &coins(200, "token"));
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let bet = Bet{ amount: Coin { denom: "token".to_string(), amount: Uint128::from(200_u16) }, result: GameResult::Exact { num: DOUBLE_ZERO } };
+
+        let execute_msg = ExecuteMsg::Bet {bets: vec![bet], announced_bets: None, client_seed: None};
+
+        assert!(execute(deps.as_mut(), mock_env(), info, execute_msg).is_ok());
+    }
 
     #[test]
-    fn test_calculate_sum_coins_of_bets_and_check_coins_match_input() {
+    fn test_calculate_sum_coins_of_bets_and_reconcile_funds() {
         let bets = vec![
             Bet {
                 amount: Coin::new(10, "abc"),
@@ -657,15 +3144,10 @@ mod tests {
             Coin::new( 5, "def"),
         ];
 
-        let config = Config {
-            min_bet: 0,
-            max_bet: u64::MAX,
-            max_total: u64::MAX,
-            supported_denoms: vec!["abc".to_string(), "def".to_string()],
-        };
+        let config = test_config();
 
-        let result = check_coins_match_input(calculate_sum_coins_of_bets(&bets, &config).unwrap(), funds.clone());
-        assert_eq!(result, false);
+        let result = reconcile_funds(&calculate_sum_coins_of_bets(&bets, &config).unwrap(), &funds, &[], &config);
+        assert_eq!(result.unwrap_err(), ContractError::FundsMismatch { denom: "abc".to_string(), expected: Uint128::new(15), sent: Uint128::new(20) });
 
         let bets = vec![
             Bet {
@@ -687,16 +3169,39 @@ mod tests {
             Coin::new( 5, "def"),
         ];
 
-        let config = Config {
-            min_bet: 0,
-            max_bet: u64::MAX,
-            max_total: u64::MAX,
-            supported_denoms: vec!["abc".to_string(), "def".to_string()],
-        };
+        let config = test_config();
+
+        let totals = calculate_sum_coins_of_bets(&bets, &config).unwrap();
+        assert!(reconcile_funds(&totals, &funds, &[], &config).is_ok());
+
+        // a denom that was bet in but neither sent nor credited
+        let result = reconcile_funds(&totals, &coins(15, "abc"), &[], &config);
+        assert_eq!(result.unwrap_err(), ContractError::BalanceTooLow { denom: "def".to_string(), expected: Uint128::new(5), sent: Uint128::zero(), balance: Uint128::zero() });
+
+        // the balance pays for what wasn't sent, and nothing more
+        let balance = vec![Coin::new(100, "abc"), Coin::new(3, "def")];
+        let result = reconcile_funds(&totals, &[Coin::new(15, "abc"), Coin::new(2, "def")], &balance, &config);
+        assert_eq!(result.unwrap(), coins(3, "def"));
+
+        let result = reconcile_funds(&totals, &[], &balance, &config);
+        assert_eq!(result.unwrap_err(), ContractError::BalanceTooLow { denom: "def".to_string(), expected: Uint128::new(5), sent: Uint128::zero(), balance: Uint128::new(3) });
+
+        // sending more than the slip adds up to isn't covered by the balance
+        let result = reconcile_funds(&totals, &[Coin::new(16, "abc"), Coin::new(5, "def")], &balance, &config);
+        assert_eq!(result.unwrap_err(), ContractError::FundsMismatch { denom: "abc".to_string(), expected: Uint128::new(15), sent: Uint128::new(16) });
 
-        let result = check_coins_match_input(calculate_sum_coins_of_bets(&bets, &config).unwrap(), funds.clone());
-        assert_eq!(result, true);
-        //assert_eq!(result.unwrap_err(), StdError::generic_err("Input funds don't match sum of bets"));
+        // a denom that was sent but not bet in
+        let extra = vec![Coin::new(15, "abc"), Coin::new(5, "def"), Coin::new(1, "ghi")];
+        let result = reconcile_funds(&totals, &extra, &[], &config);
+        assert_eq!(result.unwrap_err(), ContractError::FundsMismatch { denom: "ghi".to_string(), expected: Uint128::zero(), sent: Uint128::new(1) });
+
+        let duplicate = vec![Coin::new(10, "abc"), Coin::new(5, "def"), Coin::new(5, "abc")];
+        let result = reconcile_funds(&totals, &duplicate, &[], &config);
+        assert_eq!(result.unwrap_err(), ContractError::DuplicateFunds { denom: "abc".to_string() });
+
+        let zero = vec![Coin::new(15, "abc"), Coin::new(5, "def"), Coin::new(0, "ghi")];
+        let result = reconcile_funds(&totals, &zero, &[], &config);
+        assert_eq!(result.unwrap_err(), ContractError::ZeroFunds { denom: "ghi".to_string() });
     }
 
     #[test]
@@ -709,32 +3214,128 @@ mod tests {
         ];
 
         let config = Config {
-            min_bet: 0,
-            max_bet: 3,
-            max_total: u64::MAX,
-            supported_denoms: vec!["def".to_string()],
+            max_bet: Uint128::from(3u128),
+            ..test_config()
         };
 
         let result = calculate_sum_coins_of_bets(&bets, &config);
 
-        assert_eq!(result.is_err(), true);
+        assert_eq!(result.unwrap_err(), ContractError::BetAboveMax { denom: "def".to_string(), amount: Uint128::new(5), max: Uint128::new(3) });
+    }
+
+    #[test]
+    fn test_bet_sum_overflow() {
+        let bets = vec![
+            Bet { amount: Coin::new(u128::MAX, "def"), result: GameResult::Red },
+            Bet { amount: Coin::new(1, "def"), result: GameResult::Black },
+        ];
+
+        let config = test_config();
+
+        assert_eq!(calculate_sum_coins_of_bets(&bets, &config).unwrap_err(), ContractError::Overflow);
+
+        // a winning payout too large to represent is an error too
+        let bets = vec![Bet { amount: Coin::new(u128::MAX, "def"), result: GameResult::Red }];
+        assert_eq!(calculate_max_payouts(&bets, &[], &[], &config).unwrap_err(), ContractError::Overflow);
+    }
+
+    #[test]
+    fn test_bet_amount_not_truncated() {
+        let bets = vec![
+            Bet {
+                amount: Coin::new(u64::MAX as u128 + 6, "def"),
+                result: GameResult::Red,
+            },
+        ];
+
+        let config = Config {
+            max_bet: Uint128::from(1000u128),
+            ..test_config()
+        };
+
+        assert!(calculate_sum_coins_of_bets(&bets, &config).is_err());
+    }
+
+    #[test]
+    fn test_denom_and_category_limits() {
+        let config = Config {
+            min_bet: Uint128::from(10u128),
+            max_bet: Uint128::from(100u128),
+            max_total: Uint128::from(1000u128),
+            supported_denoms: vec!["abc".to_string(), "def".to_string()],
+            denom_limits: vec![DenomLimits {
+                denom: "def".to_string(),
+                min_bet: Some(Uint128::from(1_000u128)),
+                max_bet: Some(Uint128::from(100_000u128)),
+                max_total: Some(Uint128::from(1_000_000u128)),
+                categories: vec![CategoryLimits {
+                    category: BetCategory::StraightUp,
+                    min_bet: None,
+                    max_bet: Some(Uint128::from(5_000u128)),
+                }],
+            }],
+            ..test_config()
+        };
+
+        let bet = |amount: u128, denom: &str, result: GameResult| vec![Bet { amount: Coin::new(amount, denom), result }];
+
+        // table wide limits
+        assert!(calculate_sum_coins_of_bets(&bet(100, "abc", GameResult::Red), &config).is_ok());
+        assert!(calculate_sum_coins_of_bets(&bet(101, "abc", GameResult::Red), &config).is_err());
+        assert!(calculate_sum_coins_of_bets(&bet(101, "abc", GameResult::Exact { num: 1 }), &config).is_err());
+
+        // denom overrides
+        assert!(calculate_sum_coins_of_bets(&bet(100, "def", GameResult::Red), &config).is_err());
+        assert!(calculate_sum_coins_of_bets(&bet(100_000, "def", GameResult::Red), &config).is_ok());
+        assert!(calculate_sum_coins_of_bets(&bet(100_000, "def", GameResult::Line { nums: (1, 2) }), &config).is_ok());
+
+        // category overrides on top of the denom
+        assert!(calculate_sum_coins_of_bets(&bet(5_000, "def", GameResult::Exact { num: 1 }), &config).is_ok());
+        assert!(calculate_sum_coins_of_bets(&bet(5_001, "def", GameResult::Exact { num: 1 }), &config).is_err());
+
+        let totals = |amount: u128, denom: &str| HashMap::from([(denom.to_string(), Uint128::new(amount))]);
+
+        assert!(reconcile_funds(&totals(1_000, "abc"), &coins(1_000, "abc"), &[], &config).is_ok());
+        assert!(reconcile_funds(&totals(1_001, "abc"), &coins(1_001, "abc"), &[], &config).is_err());
+        assert!(reconcile_funds(&totals(1_000_000, "def"), &coins(1_000_000, "def"), &[], &config).is_ok());
+        assert!(reconcile_funds(&totals(999, "def"), &coins(999, "def"), &[], &config).is_err());
+
+        // a lone bet at a category's minimum gets past the slip checks
+        let config = Config {
+            denom_limits: vec![DenomLimits {
+                denom: "def".to_string(),
+                min_bet: Some(Uint128::from(1_000u128)),
+                max_bet: None,
+                max_total: None,
+                categories: vec![CategoryLimits { category: BetCategory::Outside, min_bet: Some(Uint128::from(2_000u128)), max_bet: None }],
+            }],
+            max_bet: Uint128::MAX,
+            ..test_config()
+        };
+        config.validate().unwrap();
+        let bets = bet(2_000, "def", GameResult::Red);
+        let totals = calculate_sum_coins_of_bets(&bets, &config).unwrap();
+        assert!(reconcile_funds(&totals, &coins(2_000, "def"), &[], &config).is_ok());
+        assert!(calculate_sum_coins_of_bets(&bet(1_999, "def", GameResult::Red), &config).is_err());
     }
 
     #[test]
     fn test_bet_more_than_max_total() {
-        let funds = vec![
-            Coin::new( 5, "def"),
+        let bets = vec![
+            Bet { amount: Coin::new(3, "def"), result: GameResult::Red },
+            Bet { amount: Coin::new(2, "def"), result: GameResult::Odd },
         ];
 
         let config = Config {
-            min_bet: 0,
-            max_bet: 5,
-            max_total: 4,
-            supported_denoms: vec!["def".to_string()],
+            max_bet: Uint128::from(5u128),
+            max_total: Uint128::from(4u128),
+            ..test_config()
         };
 
-        let result = validate_amounts(&funds, config);
+        // every bet is within the maximum bet, the slip as a whole isn't
+        let totals = calculate_sum_coins_of_bets(&bets, &config).unwrap();
+        let result = reconcile_funds(&totals, &coins(5, "def"), &[], &config);
 
-        assert_eq!(result.is_err(), true);
+        assert_eq!(result.unwrap_err(), ContractError::StakeAboveMax { denom: "def".to_string(), amount: Uint128::new(5), max: Uint128::new(4) });
     }
 }
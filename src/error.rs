@@ -0,0 +1,140 @@
+use cosmwasm_std::{Coin, OverflowError, StdError, Uint128};
+use thiserror::Error;
+
+/// Everything `instantiate` and `execute` can fail with
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized,
+
+    #[error("Admin proposal expired at block {expired_at}")]
+    ProposalExpired { expired_at: u64 },
+
+    #[error("The owner can only be changed with propose_admin")]
+    OwnerNotGrantable,
+
+    #[error("Only {available} isn't reserved for players and can be withdrawn")]
+    FundsReserved { available: Coin },
+
+    #[error("Invalid config: {reason}")]
+    InvalidConfig { reason: String },
+
+    #[error("Denom {denom} isn't supported")]
+    UnsupportedDenom { denom: String },
+
+    #[error("Bet of {amount}{denom} is below the table minimum of {min}{denom}")]
+    BetBelowMin { denom: String, amount: Uint128, min: Uint128 },
+
+    #[error("Bet of {amount}{denom} is above the table maximum of {max}{denom}")]
+    BetAboveMax { denom: String, amount: Uint128, max: Uint128 },
+
+    #[error("Total stake of {amount}{denom} is above the table maximum of {max}{denom}")]
+    StakeAboveMax { denom: String, amount: Uint128, max: Uint128 },
+
+    #[error("Sent {sent}{denom} but the bets add up to {expected}{denom}")]
+    FundsMismatch { denom: String, expected: Uint128, sent: Uint128 },
+
+    #[error("Sent {sent}{denom} with {balance}{denom} credited but the bets add up to {expected}{denom}")]
+    BalanceTooLow { denom: String, expected: Uint128, sent: Uint128, balance: Uint128 },
+
+    #[error("Funds of {denom} were sent more than once")]
+    DuplicateFunds { denom: String },
+
+    #[error("Funds of {denom} were sent with a zero amount")]
+    ZeroFunds { denom: String },
+
+    #[error("A bet slip needs at least one bet")]
+    EmptySlip,
+
+    #[error("Bet on {bet} has a zero amount")]
+    ZeroBet { bet: String },
+
+    #[error("Invalid bet {bet}: {reason}")]
+    InvalidBet { bet: String, reason: String },
+
+    #[error("Table cannot cover this bet: a payout of {required} is possible but the bankroll is {available}")]
+    InsufficientBankroll { required: Coin, available: Coin },
+
+    #[error("{reason}")]
+    WrongSpinMode { reason: String },
+
+    #[error("Client seed can't be longer than {max} bytes")]
+    ClientSeedTooLong { max: usize },
+
+    #[error("No pending spin with id {spin_id}")]
+    SpinNotFound { spin_id: u64 },
+
+    #[error("Spin can't be settled before block {height}")]
+    SpinNotReady { height: u64 },
+
+    #[error("Nothing to claim")]
+    NothingToClaim,
+
+    #[error("Round {round_id} hasn't been settled yet")]
+    RoundNotSettled { round_id: u64 },
+
+    #[error("No round with id {round_id}")]
+    RoundNotFound { round_id: u64 },
+
+    #[error("There is no open round")]
+    NoOpenRound,
+
+    #[error("Round can't be closed before block {height}")]
+    RoundNotClosable { height: u64 },
+
+    #[error("There is no closed round to settle")]
+    NoClosedRound,
+
+    #[error("Pocket {pocket} isn't on the wheel")]
+    PocketOffWheel { pocket: u32 },
+
+    #[error("Betting is paused")]
+    BettingPaused,
+
+    #[error("The table is stopped")]
+    TableStopped,
+
+    #[error("Couldn't check whether {address} is a contract")]
+    CallerUnverified { address: String },
+
+    #[error("Randomness isn't available in this block")]
+    RandomnessUnavailable,
+
+    #[error("Amount overflowed")]
+    Overflow,
+
+    #[error("Can't migrate from version {from} down to {to}")]
+    Downgrade { from: String, to: String },
+
+    #[error("Can't migrate from {contract}")]
+    WrongContract { contract: String },
+}
+
+// overflows from checked math in `StdResult` code are reported as `Overflow` too
+impl From<StdError> for ContractError {
+    fn from(err: StdError) -> Self {
+        match err {
+            StdError::Overflow { .. } => ContractError::Overflow,
+            err => ContractError::Std(err),
+        }
+    }
+}
+
+// lets queries, which answer with `StdError`, share loaders with `execute`
+impl From<ContractError> for StdError {
+    fn from(err: ContractError) -> Self {
+        match err {
+            ContractError::Std(err) => err,
+            err => StdError::generic_err(err.to_string()),
+        }
+    }
+}
+
+impl From<OverflowError> for ContractError {
+    fn from(_: OverflowError) -> Self {
+        ContractError::Overflow
+    }
+}
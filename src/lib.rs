@@ -1,7 +1,10 @@
+pub mod auth;
 pub mod contract;
+pub mod error;
+pub mod migrations;
 // mod executes;
 pub mod msg;
-// mod queries;
+mod queries;
 pub mod state;
 pub mod rng;
 pub mod types;
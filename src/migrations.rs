@@ -0,0 +1,98 @@
+use std::fmt;
+use std::str::FromStr;
+
+use cosmwasm_std::{StdError, StdResult, Storage, Uint128};
+use secret_toolkit_storage::Item;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ContractError;
+use crate::state::{Config, CONFIG_KEY, save_config};
+
+pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Version of tables deployed before the version was stored
+pub const UNVERSIONED: Version = Version(0, 1, 0);
+
+/// A `major.minor.patch` contract version
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Version(pub u64, pub u64, pub u64);
+
+impl FromStr for Version {
+    type Err = StdError;
+
+    fn from_str(version: &str) -> StdResult<Self> {
+        let invalid = || StdError::generic_err(format!("Invalid contract version {}", version));
+
+        let parts = version.split('.')
+            .map(|part| part.parse::<u64>().map_err(|_| invalid()))
+            .collect::<StdResult<Vec<u64>>>()?;
+
+        match parts[..] {
+            [major, minor, patch] => Ok(Version(major, minor, patch)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+type Migration = fn(&mut dyn Storage) -> Result<(), ContractError>;
+
+/// Every change to the stored state, oldest first. A migration upgrades state written by any
+/// version below its own
+const MIGRATIONS: &[(Version, Migration)] = &[
+    (Version(0, 2, 0), migrate_legacy_config),
+];
+
+/// Brings state written by `from` up to date with this code
+pub fn migrate_state(storage: &mut dyn Storage, from: Version) -> Result<(), ContractError> {
+    for (version, migration) in MIGRATIONS {
+        if from < *version {
+            migration(storage)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Config as stored before 0.2.0, with `u64` limits and none of the later settings
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct LegacyConfig {
+    pub min_bet: u64,
+    pub max_bet: u64,
+    pub max_total: u64,
+    pub supported_denoms: Vec<String>,
+}
+
+pub static LEGACY_CONFIG_ITEM: Item<LegacyConfig> = Item::new(CONFIG_KEY.as_bytes());
+
+/// Widens the limits to `Uint128` and fills in the defaults for everything added since. `u64::MAX`
+/// was how 0.1.0 stored "no limit"
+fn migrate_legacy_config(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    let legacy = LEGACY_CONFIG_ITEM.load(storage)?;
+
+    let limit = |amount: u64| if amount == u64::MAX { Uint128::MAX } else { Uint128::from(amount) };
+
+    let config = Config {
+        min_bet: Uint128::from(legacy.min_bet),
+        max_bet: limit(legacy.max_bet),
+        max_total: limit(legacy.max_total),
+        supported_denoms: legacy.supported_denoms,
+        denom_limits: vec![],
+        wheel: Default::default(),
+        payouts: Default::default(),
+        zero_rule: Default::default(),
+        spin_mode: Default::default(),
+        circuit_breaker: None,
+        contract_callers: Default::default(),
+        payout_mode: Default::default(),
+    };
+    config.validate()?;
+
+    Ok(save_config(storage, &config)?)
+}
@@ -1,36 +1,284 @@
-use cosmwasm_std::{Addr, Coin};
+use cosmwasm_std::{Addr, Coin, Decimal, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use crate::types::Bet;
+use crate::auth::Permit;
+use crate::state::{Config, PendingAdmin, PendingSpin, Role, Round, SpinRecord};
+use crate::types::{AnnouncedBet, Bet, CircuitBreaker, ContractCallers, ContractStatus, DenomLimits, PayoutMode, PayoutTable, SpinMode, WheelVariant, ZeroRule};
 
-#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
-    pub min_bet: Option<u64>,
-    pub max_bet: Option<u64>,
-    pub max_total: Option<u64>,
+    pub min_bet: Option<Uint128>,
+    pub max_bet: Option<Uint128>,
+    pub max_total: Option<Uint128>,
     pub supported_denoms: Option<Vec<String>>,
-    pub admin: Option<Addr>
+    /// Per denom overrides of the limits above
+    pub denom_limits: Option<Vec<DenomLimits>>,
+    pub admin: Option<Addr>,
+    /// Defaults to a single zero (european) wheel
+    pub wheel: Option<WheelVariant>,
+    /// Defaults to the standard payouts, see `PayoutTable::default`
+    pub payouts: Option<PayoutTable>,
+    /// Defaults to `ZeroRule::None`
+    pub zero_rule: Option<ZeroRule>,
+    /// Defaults to `SpinMode::Instant`
+    pub spin_mode: Option<SpinMode>,
+    /// Off by default
+    pub circuit_breaker: Option<CircuitBreaker>,
+    /// Defaults to letting any contract bet
+    pub contract_callers: Option<ContractCallers>,
+    /// Defaults to `PayoutMode::Push`
+    pub payout_mode: Option<PayoutMode>,
+}
+
+/// Changes the betting limits and denoms, fields left out keep their current value. The wheel
+/// and spin mode are fixed at instantiation since pending spins were accepted under them
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct ConfigUpdate {
+    pub min_bet: Option<Uint128>,
+    pub max_bet: Option<Uint128>,
+    pub max_total: Option<Uint128>,
+    pub supported_denoms: Option<Vec<String>>,
+    pub denom_limits: Option<Vec<DenomLimits>>,
+    pub circuit_breaker: Option<CircuitBreaker>,
+    /// Turns the circuit breaker off
+    pub remove_circuit_breaker: Option<bool>,
+    pub contract_callers: Option<ContractCallers>,
+    /// Balances already credited can still be claimed after switching back to push payouts
+    pub payout_mode: Option<PayoutMode>,
+}
+
+/// Upgrades the table's state to this code's version. Downgrades are refused
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    /// Applied once the state has been migrated
+    pub config: Option<ConfigUpdate>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
+    /// Whatever part of the slip the sent funds don't cover is paid from the sender's credited
+    /// winnings, the same goes for `PlaceBets` and `PlaceRoundBets`
     Bet {
-        bets: Vec<Bet>
+        bets: Vec<Bet>,
+        /// Racetrack bets, expanded into their individual bets by the contract
+        announced_bets: Option<Vec<AnnouncedBet>>,
+        /// Mixed into the seed of the roll, so the result depends on something the player picked.
+        /// The result event commits to the full seed with `seed_commitment`
+        client_seed: Option<String>,
     },
     AdminWithdraw {
         coin: Coin
     },
-    ChangeAdmin {
-        admin: Addr
-    }
+    /// First step of handing over the table, the proposed admin has to accept within
+    /// `expiry_blocks`. A new proposal replaces the previous one
+    ProposeAdmin {
+        admin: String,
+        expiry_blocks: Option<u64>,
+    },
+    /// Roles granted by the previous owner are kept, the new owner can check them with the roles
+    /// query and revoke any they don't want
+    AcceptAdmin {},
+    CancelAdminProposal {},
+    /// Owner only
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+    /// Owner only
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+    /// Owner only
+    SetPayouts {
+        payouts: PayoutTable
+    },
+    /// Operators can change the limits and denoms, anything else needs the owner
+    UpdateConfig(ConfigUpdate),
+    /// Pausers can pause betting on a running table, the owner can set any status
+    SetStatus {
+        status: ContractStatus,
+    },
+    /// Delayed mode only - stores the bets to be rolled by a later `Settle`
+    PlaceBets {
+        bets: Vec<Bet>,
+        announced_bets: Option<Vec<AnnouncedBet>>,
+    },
+    /// Delayed mode only - rolls a pending spin and pays the player. Anyone can settle a spin
+    Settle {
+        spin_id: u64
+    },
+    /// Rounds mode only - opens the next round once the previous one is settled. Anyone can open
+    /// a round
+    OpenRound {},
+    /// Rounds mode only - adds bets to the open round
+    PlaceRoundBets {
+        bets: Vec<Bet>,
+        announced_bets: Option<Vec<AnnouncedBet>>,
+    },
+    /// Rounds mode only - stops taking bets and spins the round. Anyone can close a round once its
+    /// betting window is over
+    CloseRound {},
+    /// Rounds mode only - pays out up to `limit` entries of the closed round. Repeat until the
+    /// round is settled
+    SettleRound {
+        limit: Option<u32>
+    },
+    /// Generates a viewing key for the sender's private queries, returned in the response data
+    CreateViewingKey {
+        entropy: String
+    },
+    SetViewingKey {
+        key: String
+    },
+    /// Stops a query permit the sender signed from being accepted
+    RevokePermit {
+        permit_name: String
+    },
+    /// Withdraws the sender's credited winnings, of every denom unless `denoms` is set
+    Claim {
+        denoms: Option<Vec<String>>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteAnswer {
+    CreateViewingKey {
+        key: String
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    // User that wants to read their share (todo: authentication)
-    // ReadShare {
-    //     user_index: u32
-    // }
+    /// Defaults to the latest round
+    Round {
+        round_id: Option<u64>,
+    },
+    /// Totals over every spin the table has made
+    Stats {},
+    /// The table's current settings and admin
+    Config {},
+    /// Admin proposed with `ProposeAdmin` that hasn't accepted yet
+    PendingAdmin {},
+    /// Who holds which role
+    Roles {},
+    Status {},
+    PendingSpins {
+        address: Addr,
+        /// Viewing key of `address`
+        key: String,
+        page: Option<u32>,
+        page_size: Option<u32>,
+    },
+    /// A player's settled spins, oldest first
+    History {
+        address: Addr,
+        key: String,
+        /// Id of the last spin of the previous page
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    /// Winnings credited to a player that haven't been claimed yet
+    Balance {
+        address: Addr,
+        key: String,
+    },
+    /// Private queries authenticated with a query permit instead of a viewing key
+    WithPermit {
+        permit: Permit,
+        query: QueryWithPermit,
+    },
+}
+
+/// Private queries about the account that signed the permit
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryWithPermit {
+    PendingSpins {
+        page: Option<u32>,
+        page_size: Option<u32>,
+    },
+    History {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    Balance {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PendingSpinsResponse {
+    pub spins: Vec<PendingSpin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RoundResponse {
+    pub round: Option<Round>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct HistoryResponse {
+    pub spins: Vec<SpinRecord>,
+    /// Number of spins in the player's whole history
+    pub total: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BalanceResponse {
+    pub balance: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StatsResponse {
+    pub total_spins: u64,
+    /// How often each pocket came up, indexed by pocket - 37 is 00 and 38 is 000
+    pub pocket_counts: Vec<u64>,
+    pub denoms: Vec<DenomStats>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct DenomStats {
+    pub denom: String,
+    pub wagered: Uint128,
+    pub paid_out: Uint128,
+    /// Share of the wagered amount the house kept, `(wagered - paid_out) / wagered`
+    pub house_edge: Decimal,
+    /// Set when more has been paid out than wagered, the house edge is negative then
+    pub players_ahead: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ConfigResponse {
+    pub config: Config,
+    pub admin: Addr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PendingAdminResponse {
+    pub pending: Option<PendingAdmin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RolesResponse {
+    pub owner: Addr,
+    pub treasurers: Vec<Addr>,
+    pub operators: Vec<Addr>,
+    pub pausers: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StatusResponse {
+    pub status: ContractStatus,
 }
@@ -1,51 +1,96 @@
-// use crate::msg::{CheckWinner, GameStateResponse};
-// use crate::state::GameStatus::WaitingForWinner;
-// use crate::state::{load_match_info, GameResult, GameStatus};
-// use cosmwasm_std::{Deps, Env, StdError, StdResult};
-//
-// pub fn query_game_state(deps: Deps, env: Env, game: String) -> StdResult<GameStateResponse> {
-//     let state = load_match_info(deps.storage, &game)
-//         .map_err(|_| StdError::generic_err("Game with this ID not found"))?;
-//
-//     if state.status == GameStatus::Done && (state.meta.end_game_block.unwrap() >= env.block.height)
-//     {
-//         Ok(GameStateResponse {
-//             game,
-//             state: WaitingForWinner,
-//         })
-//     } else {
-//         Ok(GameStateResponse {
-//             game,
-//             state: state.status,
-//         })
-//     }
-// }
-//
-// pub fn query_who_won(deps: Deps, env: Env, game: String) -> StdResult<CheckWinner> {
-//     let state = load_match_info(deps.storage, &game)?;
-//
-//     if state.status != GameStatus::Done {
-//         return Err(StdError::generic_err(
-//             "Players didn't finish submitting their choice!",
-//         ));
-//     }
-//
-//     if state.meta.end_game_block.unwrap() + 1 > env.block.height{
-//         return Err(StdError::generic_err("Still processing results!"));
-//     }
-//
-//     return Ok(match state.meta.winner.unwrap() {
-//         GameResult::Player1 => CheckWinner {
-//             winner: GameResult::Player1,
-//             address: Some(state.players[0].address().clone()),
-//         },
-//         GameResult::Player2 => CheckWinner {
-//             winner: GameResult::Player2,
-//             address: Some(state.players[1].address().clone()),
-//         },
-//         GameResult::Tie => CheckWinner {
-//             winner: GameResult::Tie,
-//             address: None,
-//         },
-//     });
-// }
+use crate::msg::{BalanceResponse, ConfigResponse, DenomStats, HistoryResponse, PendingAdminResponse, PendingSpinsResponse, RolesResponse, RoundResponse, StatsResponse};
+use crate::state::{load_admin, load_balance, load_config, load_latest_round, load_pending_admin, load_player_pending_spins, load_role_members, load_round, load_spin_history, load_spin_history_len, load_stats, Role};
+use cosmwasm_std::{Addr, Decimal, Deps, StdResult};
+
+pub const DEFAULT_PAGE_SIZE: u32 = 10;
+pub const MAX_PAGE_SIZE: u32 = 50;
+
+pub fn query_pending_spins(
+    deps: Deps,
+    address: Addr,
+    page: Option<u32>,
+    page_size: Option<u32>,
+) -> StdResult<PendingSpinsResponse> {
+    let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+
+    let spins = load_player_pending_spins(deps.storage, &address, page.unwrap_or_default(), page_size)?;
+
+    Ok(PendingSpinsResponse { spins })
+}
+
+pub fn query_round(deps: Deps, round_id: Option<u64>) -> StdResult<RoundResponse> {
+    let round = match round_id {
+        Some(id) => Some(load_round(deps.storage, id)?),
+        None => load_latest_round(deps.storage)?,
+    };
+
+    Ok(RoundResponse { round })
+}
+
+pub fn query_history(
+    deps: Deps,
+    address: Addr,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> StdResult<HistoryResponse> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+
+    let spins = load_spin_history(deps.storage, &address, start_after, limit)?;
+    let total = load_spin_history_len(deps.storage, &address)?;
+
+    Ok(HistoryResponse { spins, total })
+}
+
+pub fn query_balance(deps: Deps, address: Addr) -> StdResult<BalanceResponse> {
+    Ok(BalanceResponse { balance: load_balance(deps.storage, &address)? })
+}
+
+pub fn query_stats(deps: Deps) -> StdResult<StatsResponse> {
+    let config = load_config(deps.storage)?;
+    let stats = load_stats(deps.storage)?;
+
+    let mut pocket_counts = stats.pocket_counts;
+    pocket_counts.resize(pocket_counts.len().max(config.wheel.pockets() as usize), 0);
+
+    let denoms = stats.wagered.into_iter()
+        .map(|wagered| {
+            let paid_out = stats.paid_out.iter()
+                .find(|paid| paid.denom == wagered.denom)
+                .map(|paid| paid.amount)
+                .unwrap_or_default();
+
+            let players_ahead = paid_out > wagered.amount;
+            let house_edge = if wagered.amount.is_zero() {
+                Decimal::zero()
+            } else if players_ahead {
+                Decimal::from_ratio(paid_out - wagered.amount, wagered.amount)
+            } else {
+                Decimal::from_ratio(wagered.amount - paid_out, wagered.amount)
+            };
+
+            DenomStats { denom: wagered.denom, wagered: wagered.amount, paid_out, house_edge, players_ahead }
+        })
+        .collect();
+
+    Ok(StatsResponse { total_spins: stats.spins, pocket_counts, denoms })
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    Ok(ConfigResponse {
+        config: load_config(deps.storage)?,
+        admin: load_admin(deps.storage)?,
+    })
+}
+
+pub fn query_pending_admin(deps: Deps) -> StdResult<PendingAdminResponse> {
+    Ok(PendingAdminResponse { pending: load_pending_admin(deps.storage)? })
+}
+
+pub fn query_roles(deps: Deps) -> StdResult<RolesResponse> {
+    Ok(RolesResponse {
+        owner: load_admin(deps.storage)?,
+        treasurers: load_role_members(deps.storage, Role::Treasurer)?,
+        operators: load_role_members(deps.storage, Role::Operator)?,
+        pausers: load_role_members(deps.storage, Role::Pauser)?,
+    })
+}
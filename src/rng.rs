@@ -4,17 +4,12 @@ use sha2::{Digest, Sha256};
 
 
 pub struct Prng {
+    seed: [u8; 32],
     rng: ChaChaRng,
 }
 
 impl RngCore for Prng {
 
-    // fn rand_range(&mut self, min: u32, max: u32) -> u32 {
-    //     while {
-    //         self.rng.next_u32()
-    //     }
-    // }
-
     fn next_u32(&mut self) -> u32 {
         self.rng.next_u32()
     }
@@ -47,7 +42,71 @@ impl Prng {
 
         let rng = ChaChaRng::from_seed(hash_bytes);
 
-        Self { rng }
+        Self { seed: hash_bytes, rng }
+    }
+
+    /// An independent stream for `name`, derived from this generator's seed. Deriving the same name
+    /// twice gives the same stream, and drawing from it doesn't move this one
+    pub fn derive(&self, name: &str) -> Self {
+        let mut seed = b"secret-roulette/derive".to_vec();
+        seed.extend_from_slice(&self.seed);
+        seed.extend_from_slice(name.as_bytes());
+
+        Self::new(&seed)
+    }
+
+    /// Uniform number in `lo..hi`, or `lo` if the range is empty. Draws that would favour the low
+    /// end of the range are rejected rather than folded in with a modulo
+    pub fn gen_range(&mut self, lo: u32, hi: u32) -> u32 {
+        if hi <= lo {
+            return lo;
+        }
+
+        lo + self.below(u64::from(hi - lo)) as u32
+    }
+
+    /// Index picked with probability proportional to its weight, `None` if every weight is zero
+    pub fn choose_weighted(&mut self, weights: &[u32]) -> Option<usize> {
+        let total: u64 = weights.iter().map(|w| u64::from(*w)).sum();
+
+        if total == 0 {
+            return None;
+        }
+
+        let mut pick = self.below(total);
+
+        for (index, weight) in weights.iter().enumerate() {
+            let weight = u64::from(*weight);
+
+            if pick < weight {
+                return Some(index);
+            }
+            pick -= weight;
+        }
+
+        None
+    }
+
+    /// Fisher-Yates shuffle, every ordering is equally likely
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+
+    /// Uniform number in `0..bound`, `bound` must not be zero
+    fn below(&mut self, bound: u64) -> u64 {
+        // 2^64 % bound - drawing below this would leave a partial block of values at the low end
+        let threshold = bound.wrapping_neg() % bound;
+
+        loop {
+            let draw = self.rng.next_u64();
+
+            if draw >= threshold {
+                return draw % bound;
+            }
+        }
     }
 
     pub fn rand_bytes(&mut self) -> [u8; 32] {
@@ -60,4 +119,108 @@ impl Prng {
     pub fn set_word_pos(&mut self, count: u32) {
         self.rng.set_word_pos(count.into());
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pearson's chi-squared statistic of the observed counts against a uniform distribution
+    fn chi_squared(counts: &[u32]) -> f64 {
+        let total: u32 = counts.iter().sum();
+        let expected = f64::from(total) / counts.len() as f64;
+
+        counts.iter().map(|c| (f64::from(*c) - expected).powi(2) / expected).sum()
+    }
+
+    #[test]
+    fn gen_range_stays_in_range() {
+        let mut prng = Prng::new(b"range");
+
+        for _ in 0..10_000 {
+            let n = prng.gen_range(5, 42);
+            assert!((5..42).contains(&n));
+        }
+
+        assert_eq!(prng.gen_range(7, 7), 7);
+        assert_eq!(prng.gen_range(7, 3), 7);
+        assert_eq!(prng.gen_range(0, 1), 0);
+    }
+
+    #[test]
+    fn gen_range_uniform() {
+        let mut prng = Prng::new(b"uniform");
+        let mut counts = [0u32; 37];
+
+        for _ in 0..37_000 {
+            counts[prng.gen_range(0, 37) as usize] += 1;
+        }
+
+        // 36 degrees of freedom, critical value at p = 0.001
+        assert!(chi_squared(&counts) < 67.99, "{:?}", counts);
+    }
+
+    #[test]
+    fn choose_weighted_follows_weights() {
+        let mut prng = Prng::new(b"weighted");
+        let weights = [1, 0, 3, 6];
+        let mut counts = [0u32; 4];
+
+        for _ in 0..10_000 {
+            counts[prng.choose_weighted(&weights).unwrap()] += 1;
+        }
+
+        assert_eq!(counts[1], 0);
+
+        // 1:3:6 over the non zero weights, 2 degrees of freedom, critical value at p = 0.001
+        let observed = [counts[0], counts[2], counts[3]];
+        let expected = [1_000.0, 3_000.0, 6_000.0];
+        let statistic: f64 = observed.iter().zip(expected)
+            .map(|(o, e)| (f64::from(*o) - e).powi(2) / e)
+            .sum();
+        assert!(statistic < 13.82, "{:?}", counts);
+
+        assert_eq!(prng.choose_weighted(&[]), None);
+        assert_eq!(prng.choose_weighted(&[0, 0]), None);
+    }
+
+    #[test]
+    fn shuffle_uniform() {
+        let mut prng = Prng::new(b"shuffle");
+        let mut counts = [0u32; 6];
+
+        // every permutation of 3 items, indexed by where the items end up
+        for _ in 0..6_000 {
+            let mut items = [0, 1, 2];
+            prng.shuffle(&mut items);
+
+            let index = match items {
+                [0, 1, 2] => 0,
+                [0, 2, 1] => 1,
+                [1, 0, 2] => 2,
+                [1, 2, 0] => 3,
+                [2, 0, 1] => 4,
+                _ => 5,
+            };
+            counts[index] += 1;
+        }
+
+        // 5 degrees of freedom, critical value at p = 0.001
+        assert!(chi_squared(&counts) < 20.52, "{:?}", counts);
+    }
+
+    #[test]
+    fn derived_streams() {
+        let mut prng = Prng::new(b"derive");
+
+        let mut spin = prng.derive("spin");
+        let mut spin_again = prng.derive("spin");
+        let mut other = prng.derive("other");
+
+        let first = spin.next_u64();
+        assert_eq!(first, spin_again.next_u64());
+        assert_ne!(first, other.next_u64());
+
+        // deriving doesn't move the parent stream
+        assert_eq!(prng.next_u64(), Prng::new(b"derive").next_u64());
+    }
+}
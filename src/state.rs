@@ -1,19 +1,311 @@
-use secret_toolkit_storage::Item;
-use cosmwasm_std::{StdResult, Storage, Addr};
+use secret_toolkit_storage::{AppendStore, Item, Keymap};
+use cosmwasm_std::{StdError, StdResult, Storage, Addr, Coin, Timestamp, Uint128};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use crate::error::ContractError;
+use crate::types::{AnnouncedBet, Bet, BetCategory, CircuitBreaker, ContractCallers, ContractStatus, DenomLimits, PayoutMode, PayoutTable, SpinMode, WheelVariant, ZeroRule};
 
 pub static CONFIG_KEY: &str = "config";
 pub static ADMIN_KEY: &str = "admin";
+pub static PENDING_ADMIN_KEY: &str = "pending_admin";
+pub static ROLE_MEMBERS_KEY: &str = "role_members";
+pub static PRISON_KEY: &str = "prison";
+pub static SPIN_ID_KEY: &str = "spin_id";
+pub static PENDING_SPIN_KEY: &str = "pending_spin";
+pub static PLAYER_PENDING_SPINS_KEY: &str = "player_pending_spins";
+pub static RESERVED_KEY: &str = "reserved";
+pub static ROUND_ID_KEY: &str = "round_id";
+pub static ROUND_KEY: &str = "round";
+pub static ROUND_ENTRIES_KEY: &str = "round_entries";
+pub static ROUND_LIABILITIES_KEY: &str = "round_liabilities";
+pub static ROLL_NONCE_KEY: &str = "roll_nonce";
+pub static HISTORY_KEY: &str = "history";
+pub static VIEWING_KEY_KEY: &str = "viewing_key";
+pub static REVOKED_PERMITS_KEY: &str = "revoked_permits";
+pub static STATS_KEY: &str = "stats";
+pub static STATUS_KEY: &str = "status";
+pub static LOSS_WINDOW_KEY: &str = "loss_window";
+pub static CONTRACT_VERSION_KEY: &str = "contract_version";
+pub static BALANCE_KEY: &str = "balance";
 
 pub static CONFIG_ITEM: Item<Config> = Item::new(CONFIG_KEY.as_bytes());
 pub static ADMIN_ITEM: Item<Addr> = Item::new(ADMIN_KEY.as_bytes());
+pub static PENDING_ADMIN_ITEM: Item<PendingAdmin> = Item::new(PENDING_ADMIN_KEY.as_bytes());
+/// Holders of each role, suffixed by the role
+pub static ROLE_MEMBERS: Keymap<Addr, bool> = Keymap::new(ROLE_MEMBERS_KEY.as_bytes());
+/// Even money bets held under the en prison rule, suffixed by the player's address
+pub static PRISON_ITEM: Item<Vec<Bet>> = Item::new(PRISON_KEY.as_bytes());
+pub static SPIN_ID_ITEM: Item<u64> = Item::new(SPIN_ID_KEY.as_bytes());
+/// Spins waiting to be settled, suffixed by the spin id
+pub static PENDING_SPIN_ITEM: Item<PendingSpin> = Item::new(PENDING_SPIN_KEY.as_bytes());
+/// Ids of a player's pending spins, suffixed by the player's address
+pub static PLAYER_PENDING_SPINS: Keymap<u64, bool> = Keymap::new(PLAYER_PENDING_SPINS_KEY.as_bytes());
+/// Funds set aside to pay out the worst case of every pending spin
+pub static RESERVED_ITEM: Item<Vec<Coin>> = Item::new(RESERVED_KEY.as_bytes());
+/// Number of rolls made so far, mixed into every roll's seed
+pub static ROLL_NONCE_ITEM: Item<u64> = Item::new(ROLL_NONCE_KEY.as_bytes());
+/// Every spin a player has made, suffixed by the player's address
+pub static HISTORY: AppendStore<SpinRecord> = AppendStore::new(HISTORY_KEY.as_bytes());
+/// Hash of each account's viewing key, suffixed by the account's address
+pub static VIEWING_KEY_ITEM: Item<[u8; 32]> = Item::new(VIEWING_KEY_KEY.as_bytes());
+/// Names of the permits an account has revoked, suffixed by the account's address
+pub static REVOKED_PERMITS: Keymap<String, bool> = Keymap::new(REVOKED_PERMITS_KEY.as_bytes());
+pub static STATS_ITEM: Item<Stats> = Item::new(STATS_KEY.as_bytes());
+pub static STATUS_ITEM: Item<ContractStatus> = Item::new(STATUS_KEY.as_bytes());
+/// What was wagered and paid out in the circuit breaker's current window
+pub static LOSS_WINDOW_ITEM: Item<LossWindow> = Item::new(LOSS_WINDOW_KEY.as_bytes());
+/// Name and version of the code that last wrote the state. Missing on tables deployed before 0.2.0
+pub static CONTRACT_VERSION_ITEM: Item<ContractVersion> = Item::new(CONTRACT_VERSION_KEY.as_bytes());
+/// Winnings waiting to be claimed, suffixed by the player's address
+pub static BALANCE_ITEM: Item<Vec<Coin>> = Item::new(BALANCE_KEY.as_bytes());
+/// Id of the latest round
+pub static ROUND_ID_ITEM: Item<u64> = Item::new(ROUND_ID_KEY.as_bytes());
+/// Rounds, suffixed by the round id
+pub static ROUND_ITEM: Item<Round> = Item::new(ROUND_KEY.as_bytes());
+/// Every player's bets in a round, suffixed by the round id
+pub static ROUND_ENTRIES: Keymap<Addr, RoundEntry> = Keymap::new(ROUND_ENTRIES_KEY.as_bytes());
+/// What the round pays out on each pocket, suffixed by the round id
+pub static ROUND_LIABILITIES_ITEM: Item<Vec<Vec<Coin>>> = Item::new(ROUND_LIABILITIES_KEY.as_bytes());
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 pub struct Config {
-    pub min_bet: u64,
-    pub max_bet: u64,
-    pub max_total: u64,
-    pub supported_denoms: Vec<String>
+    pub min_bet: Uint128,
+    pub max_bet: Uint128,
+    pub max_total: Uint128,
+    pub supported_denoms: Vec<String>,
+    pub denom_limits: Vec<DenomLimits>,
+    pub wheel: WheelVariant,
+    pub payouts: PayoutTable,
+    pub zero_rule: ZeroRule,
+    pub spin_mode: SpinMode,
+    pub circuit_breaker: Option<CircuitBreaker>,
+    pub contract_callers: ContractCallers,
+    pub payout_mode: PayoutMode,
+}
+
+/// What an address is allowed to do on the table
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// The admin - manages roles and holds all of them. Changed with `ProposeAdmin`
+    Owner,
+    /// Can withdraw from the bankroll
+    Treasurer,
+    /// Can change limits and denoms
+    Operator,
+    /// Can halt betting
+    Pauser,
+}
+
+impl Role {
+    fn storage_suffix(&self) -> &'static [u8] {
+        match self {
+            Role::Owner => b"owner",
+            Role::Treasurer => b"treasurer",
+            Role::Operator => b"operator",
+            Role::Pauser => b"pauser",
+        }
+    }
+}
+
+/// An admin handover waiting to be accepted
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PendingAdmin {
+    pub address: Addr,
+    /// Last block the proposal can be accepted in
+    pub expires_at: u64,
+}
+
+/// Bets placed in delayed mode, waiting for a later block to roll them
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PendingSpin {
+    pub id: u64,
+    pub player: Addr,
+    pub bets: Vec<Bet>,
+    pub announced_bets: Vec<AnnouncedBet>,
+    /// Everything staked on the spin, sent or paid from the balance, refunded if it expires
+    pub funds: Vec<Coin>,
+    pub placed_height: u64,
+    /// First block that can settle the spin
+    pub target_height: u64,
+    /// Last block that can settle the spin, after it the stake can only be refunded
+    pub expires_at: u64,
+    /// Worst case payout set aside for the spin
+    pub reserved: Vec<Coin>,
+    /// Bets the player had held in prison when placing the spin, they're resolved by its roll
+    #[serde(default)]
+    pub imprisoned: Vec<Bet>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct ContractVersion {
+    pub contract: String,
+    pub version: String,
+}
+
+/// Running totals over every spin the table has made
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+    pub spins: u64,
+    /// How often each pocket came up, indexed by pocket
+    pub pocket_counts: Vec<u64>,
+    pub wagered: Vec<Coin>,
+    pub paid_out: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq)]
+pub struct LossWindow {
+    pub started_at: u64,
+    pub wagered: Vec<Coin>,
+    pub paid_out: Vec<Coin>,
+}
+
+/// A settled spin in a player's history
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct SpinRecord {
+    /// Position in the player's history, starting at 0
+    pub id: u32,
+    pub block_height: u64,
+    pub block_time: Timestamp,
+    pub bets: Vec<Bet>,
+    pub announced_bets: Vec<AnnouncedBet>,
+    pub result: u32,
+    pub payouts: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundStatus {
+    /// Taking bets
+    Open,
+    /// Rolled, with entries still waiting to be paid
+    Closed,
+    /// Every entry has been paid
+    Settled,
+}
+
+/// A shared round of bets, resolved by a single spin
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Round {
+    pub id: u64,
+    pub status: RoundStatus,
+    pub opened_height: u64,
+    /// First block that can close the round, bets are taken until the block before it
+    pub closes_at: u64,
+    pub result: Option<u32>,
+    pub entries: u32,
+    pub settled_entries: u32,
+    /// Worst case payout of the round that is still set aside
+    pub reserved: Vec<Coin>,
+}
+
+/// A player's bets in a round. Bets placed by the same player in one round are merged into a
+/// single entry
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct RoundEntry {
+    pub bets: Vec<Bet>,
+    pub announced_bets: Vec<AnnouncedBet>,
+}
+
+fn invalid_config(reason: impl Into<String>) -> ContractError {
+    ContractError::InvalidConfig { reason: reason.into() }
+}
+
+impl Config {
+    /// Rejects settings that contradict each other or can't be played with. Used whenever the
+    /// config is written
+    pub fn validate(&self) -> Result<(), ContractError> {
+        if self.supported_denoms.is_empty() {
+            return Err(invalid_config("At least one denom must be supported"));
+        }
+
+        for (i, denom) in self.supported_denoms.iter().enumerate() {
+            if self.supported_denoms[..i].contains(denom) {
+                return Err(invalid_config(format!("Denom {} is listed more than once", denom)));
+            }
+        }
+
+        for (i, limits) in self.denom_limits.iter().enumerate() {
+            if !self.supported_denoms.contains(&limits.denom) {
+                return Err(invalid_config(format!("Limits are set for {} which isn't a supported denom", limits.denom)));
+            }
+
+            if self.denom_limits[..i].iter().any(|other| other.denom == limits.denom) {
+                return Err(invalid_config(format!("Limits for {} are set more than once", limits.denom)));
+            }
+
+            // a looser category would let a lone bet through that the slip checks turn away
+            let min_bet = limits.min_bet.unwrap_or(self.min_bet);
+            let max_bet = limits.max_bet.unwrap_or(self.max_bet);
+            for category in &limits.categories {
+                if category.min_bet.is_some_and(|min| min < min_bet) || category.max_bet.is_some_and(|max| max > max_bet) {
+                    return Err(invalid_config(format!(
+                        "Limits for {:?} bets on {} can only be tighter than the limits for {}", category.category, limits.denom, limits.denom
+                    )));
+                }
+            }
+        }
+
+        for denom in &self.supported_denoms {
+            for category in [BetCategory::StraightUp, BetCategory::Inside, BetCategory::Outside] {
+                let (min_bet, max_bet) = self.bet_limits(denom, category);
+
+                if min_bet > max_bet {
+                    return Err(invalid_config(format!(
+                        "Minimum bet {}{} is above the maximum bet {}{}", min_bet, denom, max_bet, denom
+                    )));
+                }
+            }
+
+            if self.min_bet(denom) > self.max_total(denom) {
+                return Err(invalid_config(format!("Minimum bet for {} is above the maximum total", denom)));
+            }
+        }
+
+        self.payouts.validate().map_err(invalid_config)?;
+
+        if let Some(CircuitBreaker { window_blocks: 0, .. }) = self.circuit_breaker {
+            return Err(invalid_config("The circuit breaker window must be at least one block"));
+        }
+
+        match self.spin_mode {
+            SpinMode::Delayed { delay_blocks: 0, .. } =>
+                Err(invalid_config("Delayed spins must be settled at least one block later")),
+            SpinMode::Rounds { round_blocks: 0 } =>
+                Err(invalid_config("Rounds must take bets for at least one block")),
+            _ => Ok(()),
+        }
+    }
+
+    fn limits_for(&self, denom: &str) -> Option<&DenomLimits> {
+        self.denom_limits.iter().find(|limits| limits.denom == denom)
+    }
+
+    /// Smallest and largest single bet allowed for a denom and category
+    pub fn bet_limits(&self, denom: &str, category: BetCategory) -> (Uint128, Uint128) {
+        let mut min_bet = self.min_bet;
+        let mut max_bet = self.max_bet;
+
+        if let Some(limits) = self.limits_for(denom) {
+            min_bet = limits.min_bet.unwrap_or(min_bet);
+            max_bet = limits.max_bet.unwrap_or(max_bet);
+
+            if let Some(category_limits) = limits.categories.iter().find(|c| c.category == category) {
+                min_bet = category_limits.min_bet.unwrap_or(min_bet);
+                max_bet = category_limits.max_bet.unwrap_or(max_bet);
+            }
+        }
+
+        (min_bet, max_bet)
+    }
+
+    pub fn min_bet(&self, denom: &str) -> Uint128 {
+        self.limits_for(denom).and_then(|limits| limits.min_bet).unwrap_or(self.min_bet)
+    }
+
+    pub fn max_total(&self, denom: &str) -> Uint128 {
+        self.limits_for(denom).and_then(|limits| limits.max_total).unwrap_or(self.max_total)
+    }
 }
 
 pub fn save_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
@@ -30,4 +322,339 @@ pub fn save_admin(storage: &mut dyn Storage, admin: &Addr) -> StdResult<()> {
 
 pub fn load_admin(storage: &dyn Storage) -> StdResult<Addr> {
     ADMIN_ITEM.load(storage)
-}
\ No newline at end of file
+}
+
+pub fn grant_role(storage: &mut dyn Storage, role: Role, address: &Addr) -> StdResult<()> {
+    ROLE_MEMBERS.add_suffix(role.storage_suffix()).insert(storage, address, &true)
+}
+
+pub fn revoke_role(storage: &mut dyn Storage, role: Role, address: &Addr) -> StdResult<()> {
+    ROLE_MEMBERS.add_suffix(role.storage_suffix()).remove(storage, address)
+}
+
+pub fn has_role(storage: &dyn Storage, role: Role, address: &Addr) -> StdResult<bool> {
+    Ok(ROLE_MEMBERS.add_suffix(role.storage_suffix()).contains(storage, address))
+}
+
+pub fn load_role_members(storage: &dyn Storage, role: Role) -> StdResult<Vec<Addr>> {
+    ROLE_MEMBERS.add_suffix(role.storage_suffix()).iter_keys(storage)?.collect()
+}
+
+pub fn save_pending_admin(storage: &mut dyn Storage, pending: &PendingAdmin) -> StdResult<()> {
+    PENDING_ADMIN_ITEM.save(storage, pending)
+}
+
+pub fn load_pending_admin(storage: &dyn Storage) -> StdResult<Option<PendingAdmin>> {
+    PENDING_ADMIN_ITEM.may_load(storage)
+}
+
+pub fn remove_pending_admin(storage: &mut dyn Storage) {
+    PENDING_ADMIN_ITEM.remove(storage)
+}
+pub fn save_imprisoned_bets(storage: &mut dyn Storage, player: &Addr, bets: &Vec<Bet>) -> StdResult<()> {
+    let prison = PRISON_ITEM.add_suffix(player.as_bytes());
+
+    if bets.is_empty() {
+        prison.remove(storage);
+        return Ok(());
+    }
+
+    prison.save(storage, bets)
+}
+
+pub fn load_imprisoned_bets(storage: &dyn Storage, player: &Addr) -> StdResult<Vec<Bet>> {
+    Ok(PRISON_ITEM.add_suffix(player.as_bytes()).may_load(storage)?.unwrap_or_default())
+}
+
+/// Takes the player's held bets out of prison, for the spin that's going to resolve them
+pub fn take_imprisoned_bets(storage: &mut dyn Storage, player: &Addr) -> StdResult<Vec<Bet>> {
+    let bets = load_imprisoned_bets(storage, player)?;
+    save_imprisoned_bets(storage, player, &vec![])?;
+    Ok(bets)
+}
+
+/// Puts bets in prison next to any the player already has held there
+pub fn imprison_bets(storage: &mut dyn Storage, player: &Addr, bets: &[Bet]) -> StdResult<()> {
+    let mut held = load_imprisoned_bets(storage, player)?;
+    held.extend_from_slice(bets);
+    save_imprisoned_bets(storage, player, &held)
+}
+
+pub fn next_spin_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let id = SPIN_ID_ITEM.may_load(storage)?.unwrap_or_default() + 1;
+    SPIN_ID_ITEM.save(storage, &id)?;
+
+    Ok(id)
+}
+
+pub fn next_roll_nonce(storage: &mut dyn Storage) -> StdResult<u64> {
+    let nonce = ROLL_NONCE_ITEM.may_load(storage)?.unwrap_or_default() + 1;
+    ROLL_NONCE_ITEM.save(storage, &nonce)?;
+
+    Ok(nonce)
+}
+
+/// Adds a spin to the end of the player's history, numbering it as it goes
+pub fn push_spin_record(storage: &mut dyn Storage, player: &Addr, mut record: SpinRecord) -> StdResult<()> {
+    let history = HISTORY.add_suffix(player.as_bytes());
+
+    record.id = history.get_len(storage)?;
+    history.push(storage, &record)
+}
+
+/// Up to `limit` spins from the player's history, oldest first, after the spin with id `start_after`
+pub fn load_spin_history(storage: &dyn Storage, player: &Addr, start_after: Option<u32>, limit: u32) -> StdResult<Vec<SpinRecord>> {
+    let start = start_after.map(|id| id.saturating_add(1)).unwrap_or_default();
+
+    HISTORY.add_suffix(player.as_bytes())
+        .iter(storage)?
+        .skip(start as usize)
+        .take(limit as usize)
+        .collect()
+}
+
+pub fn load_spin_history_len(storage: &dyn Storage, player: &Addr) -> StdResult<u32> {
+    HISTORY.add_suffix(player.as_bytes()).get_len(storage)
+}
+
+pub fn save_viewing_key_hash(storage: &mut dyn Storage, account: &Addr, hash: &[u8; 32]) -> StdResult<()> {
+    VIEWING_KEY_ITEM.add_suffix(account.as_bytes()).save(storage, hash)
+}
+
+pub fn load_viewing_key_hash(storage: &dyn Storage, account: &Addr) -> StdResult<Option<[u8; 32]>> {
+    VIEWING_KEY_ITEM.add_suffix(account.as_bytes()).may_load(storage)
+}
+
+pub fn revoke_permit(storage: &mut dyn Storage, account: &Addr, permit_name: &str) -> StdResult<()> {
+    REVOKED_PERMITS.add_suffix(account.as_bytes()).insert(storage, &permit_name.to_string(), &true)
+}
+
+pub fn is_permit_revoked(storage: &dyn Storage, account: &Addr, permit_name: &str) -> StdResult<bool> {
+    Ok(REVOKED_PERMITS.add_suffix(account.as_bytes()).contains(storage, &permit_name.to_string()))
+}
+
+pub fn save_pending_spin(storage: &mut dyn Storage, spin: &PendingSpin) -> StdResult<()> {
+    PENDING_SPIN_ITEM.add_suffix(&spin.id.to_be_bytes()).save(storage, spin)?;
+    PLAYER_PENDING_SPINS.add_suffix(spin.player.as_bytes()).insert(storage, &spin.id, &true)
+}
+
+pub fn load_pending_spin(storage: &dyn Storage, id: u64) -> Result<PendingSpin, ContractError> {
+    PENDING_SPIN_ITEM.add_suffix(&id.to_be_bytes()).may_load(storage)?
+        .ok_or(ContractError::SpinNotFound { spin_id: id })
+}
+
+pub fn remove_pending_spin(storage: &mut dyn Storage, spin: &PendingSpin) -> StdResult<()> {
+    PENDING_SPIN_ITEM.add_suffix(&spin.id.to_be_bytes()).remove(storage);
+    PLAYER_PENDING_SPINS.add_suffix(spin.player.as_bytes()).remove(storage, &spin.id)
+}
+
+pub fn load_player_pending_spins(storage: &dyn Storage, player: &Addr, page: u32, page_size: u32) -> StdResult<Vec<PendingSpin>> {
+    PLAYER_PENDING_SPINS.add_suffix(player.as_bytes())
+        .paging_keys(storage, page, page_size)?
+        .into_iter()
+        .map(|id| Ok(load_pending_spin(storage, id)?))
+        .collect()
+}
+
+pub fn load_reserved(storage: &dyn Storage) -> StdResult<Vec<Coin>> {
+    Ok(RESERVED_ITEM.may_load(storage)?.unwrap_or_default())
+}
+
+pub fn load_reserved_amount(storage: &dyn Storage, denom: &str) -> StdResult<Uint128> {
+    Ok(load_reserved(storage)?.into_iter().find(|r| r.denom == denom).map(|r| r.amount).unwrap_or_default())
+}
+
+/// Adds `coins` into `into`, one entry per denom
+pub fn add_coins(into: &mut Vec<Coin>, coins: &[Coin]) -> StdResult<()> {
+    for coin in coins {
+        match into.iter_mut().find(|c| c.denom == coin.denom) {
+            Some(c) => c.amount = c.amount.checked_add(coin.amount)?,
+            None => into.push(coin.clone()),
+        }
+    }
+
+    Ok(())
+}
+
+pub fn reserve_funds(storage: &mut dyn Storage, coins: &[Coin]) -> StdResult<()> {
+    let mut reserved = load_reserved(storage)?;
+    add_coins(&mut reserved, coins)?;
+
+    RESERVED_ITEM.save(storage, &reserved)
+}
+
+pub fn release_funds(storage: &mut dyn Storage, coins: &[Coin]) -> StdResult<()> {
+    let mut reserved = load_reserved(storage)?;
+
+    for coin in coins {
+        if let Some(r) = reserved.iter_mut().find(|r| r.denom == coin.denom) {
+            r.amount = r.amount.saturating_sub(coin.amount);
+        }
+    }
+    reserved.retain(|r| !r.amount.is_zero());
+
+    RESERVED_ITEM.save(storage, &reserved)
+}
+
+pub fn open_round(storage: &mut dyn Storage, opened_height: u64, closes_at: u64) -> StdResult<Round> {
+    let id = ROUND_ID_ITEM.may_load(storage)?.unwrap_or_default() + 1;
+    ROUND_ID_ITEM.save(storage, &id)?;
+
+    let round = Round {
+        id,
+        status: RoundStatus::Open,
+        opened_height,
+        closes_at,
+        result: None,
+        entries: 0,
+        settled_entries: 0,
+        reserved: vec![],
+    };
+    save_round(storage, &round)?;
+
+    Ok(round)
+}
+
+pub fn save_round(storage: &mut dyn Storage, round: &Round) -> StdResult<()> {
+    ROUND_ITEM.add_suffix(&round.id.to_be_bytes()).save(storage, round)
+}
+
+pub fn load_round(storage: &dyn Storage, id: u64) -> Result<Round, ContractError> {
+    ROUND_ITEM.add_suffix(&id.to_be_bytes()).may_load(storage)?
+        .ok_or(ContractError::RoundNotFound { round_id: id })
+}
+
+pub fn load_latest_round(storage: &dyn Storage) -> Result<Option<Round>, ContractError> {
+    match ROUND_ID_ITEM.may_load(storage)? {
+        Some(id) => Ok(Some(load_round(storage, id)?)),
+        None => Ok(None),
+    }
+}
+
+pub fn load_round_entry(storage: &dyn Storage, round_id: u64, player: &Addr) -> StdResult<Option<RoundEntry>> {
+    Ok(ROUND_ENTRIES.add_suffix(&round_id.to_be_bytes()).get(storage, player))
+}
+
+pub fn save_round_entry(storage: &mut dyn Storage, round_id: u64, player: &Addr, entry: &RoundEntry) -> StdResult<()> {
+    ROUND_ENTRIES.add_suffix(&round_id.to_be_bytes()).insert(storage, player, entry)
+}
+
+/// Up to `limit` entries of a round, starting from the `start`th entry placed
+pub fn load_round_entries(storage: &dyn Storage, round_id: u64, start: u32, limit: u32) -> StdResult<Vec<(Addr, RoundEntry)>> {
+    ROUND_ENTRIES.add_suffix(&round_id.to_be_bytes())
+        .iter(storage)?
+        .skip(start as usize)
+        .take(limit as usize)
+        .collect()
+}
+
+pub fn load_round_liabilities(storage: &dyn Storage, round_id: u64) -> StdResult<Vec<Vec<Coin>>> {
+    Ok(ROUND_LIABILITIES_ITEM.add_suffix(&round_id.to_be_bytes()).may_load(storage)?.unwrap_or_default())
+}
+
+pub fn save_round_liabilities(storage: &mut dyn Storage, round_id: u64, liabilities: &Vec<Vec<Coin>>) -> StdResult<()> {
+    ROUND_LIABILITIES_ITEM.add_suffix(&round_id.to_be_bytes()).save(storage, liabilities)
+}
+
+pub fn remove_round_liabilities(storage: &mut dyn Storage, round_id: u64) {
+    ROUND_LIABILITIES_ITEM.add_suffix(&round_id.to_be_bytes()).remove(storage)
+}
+
+pub fn load_stats(storage: &dyn Storage) -> StdResult<Stats> {
+    Ok(STATS_ITEM.may_load(storage)?.unwrap_or_default())
+}
+
+pub fn record_roll(storage: &mut dyn Storage, result: u32) -> StdResult<()> {
+    let mut stats = load_stats(storage)?;
+
+    stats.spins += 1;
+    if stats.pocket_counts.len() <= result as usize {
+        stats.pocket_counts.resize(result as usize + 1, 0);
+    }
+    stats.pocket_counts[result as usize] += 1;
+
+    STATS_ITEM.save(storage, &stats)
+}
+
+pub fn record_wagers(storage: &mut dyn Storage, wagered: &[Coin], paid_out: &[Coin]) -> StdResult<()> {
+    let mut stats = load_stats(storage)?;
+
+    add_coins(&mut stats.wagered, wagered)?;
+    add_coins(&mut stats.paid_out, paid_out)?;
+
+    STATS_ITEM.save(storage, &stats)
+}
+
+pub fn load_balance(storage: &dyn Storage, player: &Addr) -> StdResult<Vec<Coin>> {
+    Ok(BALANCE_ITEM.add_suffix(player.as_bytes()).may_load(storage)?.unwrap_or_default())
+}
+
+pub fn save_balance(storage: &mut dyn Storage, player: &Addr, balance: &Vec<Coin>) -> StdResult<()> {
+    let item = BALANCE_ITEM.add_suffix(player.as_bytes());
+
+    if balance.is_empty() {
+        item.remove(storage);
+        Ok(())
+    } else {
+        item.save(storage, balance)
+    }
+}
+
+pub fn credit_balance(storage: &mut dyn Storage, player: &Addr, coins: &[Coin]) -> StdResult<()> {
+    let mut balance = load_balance(storage, player)?;
+    add_coins(&mut balance, coins)?;
+    save_balance(storage, player, &balance)
+}
+
+/// Takes `coins` out of a player's balance, erroring if it doesn't hold them
+pub fn debit_balance(storage: &mut dyn Storage, player: &Addr, coins: &[Coin]) -> StdResult<()> {
+    let mut balance = load_balance(storage, player)?;
+
+    for coin in coins {
+        let held = balance.iter_mut().find(|c| c.denom == coin.denom)
+            .ok_or_else(|| StdError::generic_err(format!("No {} balance to debit", coin.denom)))?;
+        held.amount = held.amount.checked_sub(coin.amount)?;
+    }
+    balance.retain(|c| !c.amount.is_zero());
+
+    save_balance(storage, player, &balance)
+}
+
+pub fn save_contract_version(storage: &mut dyn Storage, version: &ContractVersion) -> StdResult<()> {
+    CONTRACT_VERSION_ITEM.save(storage, version)
+}
+
+pub fn load_contract_version(storage: &dyn Storage) -> StdResult<Option<ContractVersion>> {
+    CONTRACT_VERSION_ITEM.may_load(storage)
+}
+
+pub fn save_status(storage: &mut dyn Storage, status: ContractStatus) -> StdResult<()> {
+    STATUS_ITEM.save(storage, &status)
+}
+
+pub fn load_status(storage: &dyn Storage) -> StdResult<ContractStatus> {
+    Ok(STATUS_ITEM.may_load(storage)?.unwrap_or_default())
+}
+
+/// Adds a spin to the loss window, starting a new window once `window_blocks` have passed since
+/// the current one started
+pub fn record_loss_window(
+    storage: &mut dyn Storage,
+    height: u64,
+    window_blocks: u64,
+    wagered: &[Coin],
+    paid_out: &[Coin],
+) -> StdResult<LossWindow> {
+    let mut window = LOSS_WINDOW_ITEM.may_load(storage)?.unwrap_or_default();
+
+    if height >= window.started_at.saturating_add(window_blocks) {
+        window = LossWindow { started_at: height, ..LossWindow::default() };
+    }
+
+    add_coins(&mut window.wagered, wagered)?;
+    add_coins(&mut window.paid_out, paid_out)?;
+
+    LOSS_WINDOW_ITEM.save(storage, &window)?;
+
+    Ok(window)
+}
@@ -1,8 +1,221 @@
 
-use cosmwasm_std::Coin;
+use cosmwasm_std::{Addr, Coin, Decimal, Uint128};
 use serde::{Serialize, Deserialize};
 use schemars::JsonSchema;
 
+/// Pocket number used for "00" on double and triple zero wheels
+pub const DOUBLE_ZERO: u32 = 37;
+/// Pocket number used for "000" on triple zero wheels
+pub const TRIPLE_ZERO: u32 = 38;
+
+/// Layout of the wheel the table is running. Pockets 1-36 are the same on every variant, the
+/// extra zero pockets are numbered after 36 (see `DOUBLE_ZERO` and `TRIPLE_ZERO`)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WheelVariant {
+    /// European wheel - 0
+    #[default]
+    SingleZero,
+    /// American wheel - 0, 00
+    DoubleZero,
+    /// 0, 00, 000
+    TripleZero,
+}
+
+impl WheelVariant {
+    /// Total number of pockets on the wheel
+    pub fn pockets(&self) -> u32 {
+        match self {
+            WheelVariant::SingleZero => 37,
+            WheelVariant::DoubleZero => 38,
+            WheelVariant::TripleZero => 39,
+        }
+    }
+
+    pub fn is_zero(&self, num: u32) -> bool {
+        num == 0 || (num > 36 && num < self.pockets())
+    }
+
+    /// Splits between a zero pocket and its neighbours on the layout
+    pub fn zero_splits(&self) -> Vec<(u32, u32)> {
+        match self {
+            WheelVariant::SingleZero => vec![(0, 1), (0, 2), (0, 3)],
+            WheelVariant::DoubleZero => vec![
+                (0, 1), (0, 2), (DOUBLE_ZERO, 2), (DOUBLE_ZERO, 3), (0, DOUBLE_ZERO)
+            ],
+            WheelVariant::TripleZero => vec![
+                (0, 1), (DOUBLE_ZERO, 2), (TRIPLE_ZERO, 3), (0, DOUBLE_ZERO), (DOUBLE_ZERO, TRIPLE_ZERO)
+            ],
+        }
+    }
+
+    /// Three number bets that include a zero pocket
+    pub fn trios(&self) -> Vec<(u32, u32, u32)> {
+        match self {
+            WheelVariant::SingleZero => vec![(0, 1, 2), (0, 2, 3)],
+            WheelVariant::DoubleZero => vec![(0, 1, 2), (0, DOUBLE_ZERO, 2), (DOUBLE_ZERO, 2, 3)],
+            WheelVariant::TripleZero => vec![(0, DOUBLE_ZERO, TRIPLE_ZERO)],
+        }
+    }
+}
+
+/// What happens to even money bets when the ball lands on a zero pocket
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ZeroRule {
+    /// Even money bets lose like every other bet
+    #[default]
+    None,
+    /// Half of the stake is returned to the player
+    LaPartage,
+    /// The stake is held for the player's next spin, and returned to them if the bet wins it
+    EnPrison,
+}
+
+/// How spins on the table are resolved
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SpinMode {
+    /// Bets are rolled in the same transaction they are placed in
+    #[default]
+    Instant,
+    /// Bets are stored by `PlaceBets` and rolled by a `Settle` at least `delay_blocks` later. Spins
+    /// not settled within `expiry_blocks` of that are refunded instead
+    Delayed {
+        delay_blocks: u64,
+        expiry_blocks: u64,
+    },
+    /// Players bet into a shared round with `PlaceRoundBets` for `round_blocks` blocks after it
+    /// opens, then a single `CloseRound` spin resolves the round for everyone
+    Rounds {
+        round_blocks: u64,
+    },
+}
+
+/// Whether the table is taking bets
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ContractStatus {
+    #[default]
+    Running,
+    /// No new bets, spins and rounds already placed can still be settled
+    BettingPaused,
+    /// No new bets and nothing is settled. Admin messages still work
+    FullyStopped,
+}
+
+/// Pauses betting by itself when the house loses more than `max_net_loss` of a denom within
+/// `window_blocks`
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct CircuitBreaker {
+    pub window_blocks: u64,
+    pub max_net_loss: Vec<Coin>,
+}
+
+/// Which contracts can bet. A contract sees a spin's result before its transaction ends and can
+/// fail it on purpose, so a table can keep them out
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ContractCallers {
+    #[default]
+    Allowed,
+    /// Only the listed contracts can bet, accounts aren't affected. An empty list keeps every
+    /// contract out
+    AllowListed {
+        contracts: Vec<Addr>,
+    },
+}
+
+/// How winnings reach the players
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PayoutMode {
+    /// Sent with the spin that won them
+    #[default]
+    Push,
+    /// Credited to the player's balance and withdrawn with `Claim`, so a spin doesn't depend on the
+    /// transfer succeeding and many small wins can be withdrawn at once. Bets can also be paid from
+    /// the balance instead of being sent
+    Claim,
+}
+
+/// Groups of bets that can be given their own limits
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BetCategory {
+    /// Bets on a single number
+    StraightUp,
+    /// Every other bet on the numbers - splits, streets, corners and the like
+    Inside,
+    /// Columns, dozens and even money bets
+    Outside,
+}
+
+/// Limits for a single denom, overriding the table wide limits in `Config`
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct DenomLimits {
+    pub denom: String,
+    pub min_bet: Option<Uint128>,
+    pub max_bet: Option<Uint128>,
+    pub max_total: Option<Uint128>,
+    /// Tighter limits for single bets of a category, e.g. a lower maximum on straight ups. They
+    /// can't go below the denom's minimum or above its maximum
+    #[serde(default)]
+    pub categories: Vec<CategoryLimits>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CategoryLimits {
+    pub category: BetCategory,
+    pub min_bet: Option<Uint128>,
+    pub max_bet: Option<Uint128>,
+}
+
+/// Human readable name of a pocket, so 37 is shown as "00"
+pub fn pocket_label(num: u32) -> String {
+    match num {
+        DOUBLE_ZERO => "00".to_string(),
+        TRIPLE_ZERO => "000".to_string(),
+        _ => num.to_string(),
+    }
+}
+
+fn pocket_labels(nums: &[u32]) -> String {
+    nums.iter().map(|n| pocket_label(*n)).collect::<Vec<String>>().join(",")
+}
+
+/// Compares two bets that cover the same numbers, regardless of the order they were given in
+fn same_numbers(a: &[u32], b: &[u32]) -> bool {
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a.sort_unstable();
+    b.sort_unstable();
+
+    a == b
+}
+
+/// Row (0-11) and column (0-2) of a number on the layout. Zero pockets sit above the layout
+/// and are handled separately
+fn layout_position(num: u32) -> Result<(u32, u32), String> {
+    if !(1..=36).contains(&num) {
+        return Err(format!("{} is not on the layout", pocket_label(num)));
+    }
+
+    Ok(((num - 1) / 3, (num - 1) % 3))
+}
+
+/// `nums` sorted, if they are `len` consecutive numbers starting at the beginning of a row
+fn is_row_run(nums: &[u32], len: u32) -> bool {
+    let mut sorted = nums.to_vec();
+    sorted.sort_unstable();
+
+    let first = sorted[0];
+    first >= 1 && first % 3 == 1 && first + len - 1 <= 36
+        && sorted.iter().enumerate().all(|(i, n)| *n == first + i as u32)
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct Bet {
@@ -37,31 +250,150 @@ pub enum GameResult {
     },
     Range1to18,
     Range19to36,
-
+    /// 0, 00, 1, 2, 3 - only available on double zero wheels
+    TopLine,
+    /// A full row of three numbers, e.g. 1, 2, 3
+    Street {
+        nums: (u32, u32, u32)
+    },
+    /// Two adjacent rows, e.g. 1 to 6
+    SixLine {
+        nums: (u32, u32, u32, u32, u32, u32)
+    },
+    /// Three numbers including a zero, e.g. 0, 1, 2
+    Trio {
+        nums: (u32, u32, u32)
+    },
+    /// First four - 0, 1, 2, 3 - only available on single zero wheels
+    Basket,
 }
 
 impl GameResult {
-    pub fn validate(&self) -> bool {
+    pub fn category(&self) -> BetCategory {
+        match self {
+            GameResult::Exact { .. } => BetCategory::StraightUp,
+            GameResult::Line { .. } | GameResult::Corner { .. } | GameResult::TopLine |
+            GameResult::Street { .. } | GameResult::SixLine { .. } | GameResult::Trio { .. } |
+            GameResult::Basket => BetCategory::Inside,
+            _ => BetCategory::Outside,
+        }
+    }
+
+    /// Bets that pay 1:1 and are covered by `ZeroRule`
+    pub fn is_even_money(&self) -> bool {
+        matches!(
+            self,
+            GameResult::Red | GameResult::Black | GameResult::Odd | GameResult::Even |
+            GameResult::Range1to18 | GameResult::Range19to36
+        )
+    }
+
+    /// Checks that the bet can actually be placed on the table, returns the reason if it can't
+    pub fn validate(&self, wheel: WheelVariant) -> Result<(), String> {
         match self {
             GameResult::Exact { num } => {
-                num < &37u32
+                if *num >= wheel.pockets() {
+                    return Err(format!("{} is not a pocket on this wheel", num));
+                }
+                Ok(())
             },
             GameResult::Line { nums: (n1, n2) } => {
-                n1 != n2
+                if n1 == n2 {
+                    return Err("a split must cover two different numbers".to_string());
+                }
+
+                if wheel.is_zero(*n1) || wheel.is_zero(*n2) {
+                    if wheel.zero_splits().iter().any(|(a, b)| same_numbers(&[*a, *b], &[*n1, *n2])) {
+                        return Ok(());
+                    }
+                    return Err(format!(
+                        "{} and {} are not a zero split on this wheel", pocket_label(*n1), pocket_label(*n2)
+                    ));
+                }
+
+                let (row1, col1) = layout_position(*n1)?;
+                let (row2, col2) = layout_position(*n2)?;
+
+                if row1 == row2 && col1.abs_diff(col2) == 1 {
+                    return Ok(());
+                }
+                if col1 == col2 && row1.abs_diff(row2) == 1 {
+                    return Ok(());
+                }
+                if row1 != row2 && col1 != col2 && n1.abs_diff(*n2) == 1 {
+                    return Err(format!("{} and {} are on different rows of the layout", n1, n2));
+                }
+
+                Err(format!("{} and {} are not next to each other on the layout", n1, n2))
             },
             GameResult::Corner { nums: (n1, n2, n3, n4) } => {
-                // too lazy to actually validate the values
-                n1 != n2 && n1 != n3 && n1 != n4 && n2 != n3 && n2 != n4 && n3 != n4
+                let nums = [*n1, *n2, *n3, *n4];
+                let mut sorted = nums.to_vec();
+                sorted.sort_unstable();
+                sorted.dedup();
+                if sorted.len() != 4 {
+                    return Err("a corner must cover four different numbers".to_string());
+                }
+
+                let positions = nums.iter().map(|n| layout_position(*n)).collect::<Result<Vec<_>, _>>()?;
+                let rows = positions.iter().map(|(row, _)| *row);
+                let cols = positions.iter().map(|(_, col)| *col);
+                let row_span = rows.clone().max().unwrap() - rows.min().unwrap();
+                let col_span = cols.clone().max().unwrap() - cols.min().unwrap();
+
+                // four different numbers inside a 2x2 box have to be exactly that box
+                if row_span == 1 && col_span == 1 {
+                    return Ok(());
+                }
+                // right shape, but starting in the last column so it spills into the next row
+                let [a, b, c, d] = [sorted[0], sorted[1], sorted[2], sorted[3]];
+                if b == a + 1 && c == a + 3 && d == a + 4 {
+                    return Err(format!("corner {} wraps around the edge of the layout", pocket_labels(&sorted)));
+                }
+
+                Err(format!("{} are not four numbers meeting at a corner", pocket_labels(&sorted)))
+            },
+            GameResult::TopLine => {
+                if wheel != WheelVariant::DoubleZero {
+                    return Err("top line is only available on double zero wheels".to_string());
+                }
+                Ok(())
+            },
+            GameResult::Street { nums: (n1, n2, n3) } => {
+                if !is_row_run(&[*n1, *n2, *n3], 3) {
+                    return Err(format!("{} is not a row of the layout", pocket_labels(&[*n1, *n2, *n3])));
+                }
+                Ok(())
+            },
+            GameResult::SixLine { nums: (n1, n2, n3, n4, n5, n6) } => {
+                let nums = [*n1, *n2, *n3, *n4, *n5, *n6];
+                if !is_row_run(&nums, 6) {
+                    return Err(format!("{} are not two adjacent rows of the layout", pocket_labels(&nums)));
+                }
+                Ok(())
+            },
+            GameResult::Trio { nums: (n1, n2, n3) } => {
+                let nums = [*n1, *n2, *n3];
+                if !wheel.trios().iter().any(|(a, b, c)| same_numbers(&[*a, *b, *c], &nums)) {
+                    return Err(format!("{} is not a trio on this wheel", pocket_labels(&nums)));
+                }
+                Ok(())
+            },
+            GameResult::Basket => {
+                if wheel != WheelVariant::SingleZero {
+                    return Err("basket is only available on single zero wheels".to_string());
+                }
+                Ok(())
             },
-            _ => true,
+            _ => Ok(()),
         }
     }
 }
 
-impl Into<String> for GameResult {
-    fn into(self) -> String {
-        match self {
-            GameResult::Exact { num } => num.to_string(),
+impl From<GameResult> for String {
+    fn from(result: GameResult) -> String {
+        match result {
+            GameResult::Exact { num } => pocket_label(num),
             GameResult::Red => "red".to_string(),
             GameResult::Black => "black".to_string(),
             GameResult::Range1to12 => "1-12".to_string(),
@@ -72,10 +404,17 @@ impl Into<String> for GameResult {
             GameResult::Range2to1First => "2to11st".to_string(),
             GameResult::Range2to1Second => "2to12nd".to_string(),
             GameResult::Range2to1Third => "2to13rd".to_string(),
-            GameResult::Line { nums } => format!("double-{}-{}", nums.0, nums.1),
+            GameResult::Line { nums } => format!("double-{}-{}", pocket_label(nums.0), pocket_label(nums.1)),
             GameResult::Corner { nums } => format!("quad-{},{},{},{}", nums.0, nums.1, nums.2, nums.3),
             GameResult::Range1to18 => "1-18".to_string(),
             GameResult::Range19to36 => "19-36".to_string(),
+            GameResult::TopLine => "top-line".to_string(),
+            GameResult::Street { nums } => format!("street-{}", pocket_labels(&[nums.0, nums.1, nums.2])),
+            GameResult::SixLine { nums } => format!(
+                "sixline-{}", pocket_labels(&[nums.0, nums.1, nums.2, nums.3, nums.4, nums.5])
+            ),
+            GameResult::Trio { nums } => format!("trio-{}", pocket_labels(&[nums.0, nums.1, nums.2])),
+            GameResult::Basket => "basket".to_string(),
         }
     }
 }
@@ -89,7 +428,7 @@ impl PartialEq for GameResult {
             (GameResult::Corner { nums: (other_a, other_b, other_c, other_d) },
                 GameResult::Corner { nums: (this_a, this_b, this_c, this_d) }
             ) => {
-                let temp_v = vec![other_a, other_b, other_c, other_d];
+                let temp_v = [other_a, other_b, other_c, other_d];
 
                 for num in [this_a, this_b, this_c, this_d] {
                     if !temp_v.contains(&num) {
@@ -111,6 +450,17 @@ impl PartialEq for GameResult {
             (GameResult::Range1to12, GameResult::Range1to12) => true,
             (GameResult::Range13to24, GameResult::Range13to24) => true,
             (GameResult::Range25to36, GameResult::Range25to36) => true,
+            (GameResult::TopLine, GameResult::TopLine) => true,
+            (GameResult::Basket, GameResult::Basket) => true,
+            (GameResult::Street { nums: (a1, a2, a3) }, GameResult::Street { nums: (b1, b2, b3) }) |
+            (GameResult::Trio { nums: (a1, a2, a3) }, GameResult::Trio { nums: (b1, b2, b3) }) => {
+                same_numbers(&[*a1, *a2, *a3], &[*b1, *b2, *b3])
+            }
+            (GameResult::SixLine { nums: (a1, a2, a3, a4, a5, a6) },
+                GameResult::SixLine { nums: (b1, b2, b3, b4, b5, b6) }
+            ) => {
+                same_numbers(&[*a1, *a2, *a3, *a4, *a5, *a6], &[*b1, *b2, *b3, *b4, *b5, *b6])
+            }
             (GameResult::Exact { num: n1 }, GameResult::Exact { num: n2 }) => {
                 n1 == n2
             },
@@ -118,32 +468,85 @@ impl PartialEq for GameResult {
         }
 
     }
+}
+
+/// Multipliers paid out for each type of winning bet. These are total return values - the stake
+/// is included, so a straight up paying 35:1 is 36
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PayoutTable {
+    pub straight_up: Decimal,
+    pub split: Decimal,
+    pub street: Decimal,
+    pub corner: Decimal,
+    pub six_line: Decimal,
+    pub trio: Decimal,
+    pub basket: Decimal,
+    pub top_line: Decimal,
+    /// 2to1 bets
+    pub column: Decimal,
+    /// 1-12, 13-24, 25-36
+    pub dozen: Decimal,
+    /// red, black, odd, even, 1-18, 19-36
+    pub even_money: Decimal,
+}
 
-    fn ne(&self, other: &Self) -> bool {
-        !self.eq(other)
+impl Default for PayoutTable {
+    fn default() -> Self {
+        Self {
+            straight_up: Decimal::from_ratio(36u128, 1u128),
+            split: Decimal::from_ratio(18u128, 1u128),
+            street: Decimal::from_ratio(12u128, 1u128),
+            corner: Decimal::from_ratio(9u128, 1u128),
+            six_line: Decimal::from_ratio(6u128, 1u128),
+            trio: Decimal::from_ratio(12u128, 1u128),
+            basket: Decimal::from_ratio(9u128, 1u128),
+            top_line: Decimal::from_ratio(7u128, 1u128),
+            column: Decimal::from_ratio(3u128, 1u128),
+            dozen: Decimal::from_ratio(3u128, 1u128),
+            even_money: Decimal::from_ratio(2u128, 1u128),
+        }
     }
 }
 
-impl GameResult {
-    pub fn payout(&self) -> u8 {
-        match self {
-            GameResult::Exact { .. } => 36,
-            GameResult::Red => 2,
-            GameResult::Black => 2,
-            GameResult::Range1to12 => 3,
-            GameResult::Range13to24 => 3,
-            GameResult::Range25to36 => 3,
-            GameResult::Odd => 2,
-            GameResult::Even => 2,
-            GameResult::Range2to1First => 3,
-            GameResult::Range2to1Second => 3,
-            GameResult::Range2to1Third => 3,
-            GameResult::Line { .. } => 18,
-            GameResult::Corner { .. } => 9,
-            GameResult::Range1to18 => 2,
-            GameResult::Range19to36 => 2,
+impl PayoutTable {
+    pub fn multiplier(&self, result: &GameResult) -> Decimal {
+        match result {
+            GameResult::Exact { .. } => self.straight_up,
+            GameResult::Red => self.even_money,
+            GameResult::Black => self.even_money,
+            GameResult::Range1to12 => self.dozen,
+            GameResult::Range13to24 => self.dozen,
+            GameResult::Range25to36 => self.dozen,
+            GameResult::Odd => self.even_money,
+            GameResult::Even => self.even_money,
+            GameResult::Range2to1First => self.column,
+            GameResult::Range2to1Second => self.column,
+            GameResult::Range2to1Third => self.column,
+            GameResult::Line { .. } => self.split,
+            GameResult::Corner { .. } => self.corner,
+            GameResult::Range1to18 => self.even_money,
+            GameResult::Range19to36 => self.even_money,
+            GameResult::TopLine => self.top_line,
+            GameResult::Street { .. } => self.street,
+            GameResult::SixLine { .. } => self.six_line,
+            GameResult::Trio { .. } => self.trio,
+            GameResult::Basket => self.basket,
         }
     }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let multipliers = [
+            self.straight_up, self.split, self.street, self.corner, self.six_line, self.trio,
+            self.basket, self.top_line, self.column, self.dozen, self.even_money,
+        ];
+
+        if multipliers.iter().any(|m| m.is_zero()) {
+            return Err("Payout multipliers must be greater than zero".to_string());
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
@@ -161,3 +564,140 @@ pub enum LineType {
     Left,
     Right
 }
+
+/// Order of the pockets around a single zero wheel, clockwise from 0
+pub const SINGLE_ZERO_WHEEL: [u32; 37] = [
+    0, 32, 15, 19, 4, 21, 2, 25, 17, 34, 6, 27, 13, 36, 11, 30, 8, 23, 10, 5, 24, 16, 33, 1, 20,
+    14, 31, 9, 22, 18, 29, 7, 28, 12, 35, 3, 26,
+];
+
+/// Call bets from the racetrack. Each one is a fixed pattern of chips on the layout
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnouncedCall {
+    /// 9 chips - the numbers between 22 and 25 around the zero
+    VoisinsDuZero,
+    /// 6 chips - the numbers between 27 and 33 opposite the zero
+    TiersDuCylindre,
+    /// 5 chips - the numbers not covered by voisins or tiers
+    Orphelins,
+    /// 4 chips - the numbers between 12 and 15 closest to the zero
+    JeuZero,
+    /// `count` numbers either side of `num` on the wheel, one chip each
+    Neighbours {
+        num: u32,
+        count: u32,
+    },
+}
+
+impl AnnouncedCall {
+    /// The bets making up the call, with the number of chips placed on each
+    pub fn chips(&self) -> Vec<(GameResult, u32)> {
+        match self {
+            AnnouncedCall::VoisinsDuZero => vec![
+                (GameResult::Trio { nums: (0, 2, 3) }, 2),
+                (GameResult::Line { nums: (4, 7) }, 1),
+                (GameResult::Line { nums: (12, 15) }, 1),
+                (GameResult::Line { nums: (18, 21) }, 1),
+                (GameResult::Line { nums: (19, 22) }, 1),
+                (GameResult::Line { nums: (32, 35) }, 1),
+                (GameResult::Corner { nums: (25, 26, 28, 29) }, 2),
+            ],
+            AnnouncedCall::TiersDuCylindre => vec![
+                (GameResult::Line { nums: (5, 8) }, 1),
+                (GameResult::Line { nums: (10, 11) }, 1),
+                (GameResult::Line { nums: (13, 16) }, 1),
+                (GameResult::Line { nums: (23, 24) }, 1),
+                (GameResult::Line { nums: (27, 30) }, 1),
+                (GameResult::Line { nums: (33, 36) }, 1),
+            ],
+            AnnouncedCall::Orphelins => vec![
+                (GameResult::Exact { num: 1 }, 1),
+                (GameResult::Line { nums: (6, 9) }, 1),
+                (GameResult::Line { nums: (14, 17) }, 1),
+                (GameResult::Line { nums: (17, 20) }, 1),
+                (GameResult::Line { nums: (31, 34) }, 1),
+            ],
+            AnnouncedCall::JeuZero => vec![
+                (GameResult::Line { nums: (0, 3) }, 1),
+                (GameResult::Line { nums: (12, 15) }, 1),
+                (GameResult::Exact { num: 26 }, 1),
+                (GameResult::Line { nums: (32, 35) }, 1),
+            ],
+            AnnouncedCall::Neighbours { num, count } => {
+                let len = SINGLE_ZERO_WHEEL.len();
+                let pos = SINGLE_ZERO_WHEEL.iter().position(|n| n == num).unwrap_or_default();
+                let count = *count as usize;
+
+                (0..=2 * count)
+                    .map(|i| SINGLE_ZERO_WHEEL[(pos + len + i - count) % len])
+                    .map(|num| (GameResult::Exact { num }, 1))
+                    .collect()
+            }
+        }
+    }
+
+    pub fn validate(&self, wheel: WheelVariant) -> Result<(), String> {
+        if wheel != WheelVariant::SingleZero {
+            return Err("announced bets are only available on single zero wheels".to_string());
+        }
+
+        if let AnnouncedCall::Neighbours { num, count } = self {
+            if *num > 36 {
+                return Err(format!("{} is not a pocket on this wheel", num));
+            }
+            if *count == 0 || *count > 18 {
+                return Err("neighbours must cover between 1 and 18 numbers either side".to_string());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<AnnouncedCall> for String {
+    fn from(call: AnnouncedCall) -> String {
+        match call {
+            AnnouncedCall::VoisinsDuZero => "voisins".to_string(),
+            AnnouncedCall::TiersDuCylindre => "tiers".to_string(),
+            AnnouncedCall::Orphelins => "orphelins".to_string(),
+            AnnouncedCall::JeuZero => "jeu-zero".to_string(),
+            AnnouncedCall::Neighbours { num, count } => format!("neighbours-{}-{}", count, num),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AnnouncedBet {
+    /// Total stake for the call, split evenly over its chips
+    pub amount: Coin,
+    pub call: AnnouncedCall,
+}
+
+impl AnnouncedBet {
+    /// Splits the stake into the individual bets on the layout
+    pub fn expand(&self, wheel: WheelVariant) -> Result<Vec<Bet>, String> {
+        self.call.validate(wheel)?;
+
+        if self.amount.amount.is_zero() {
+            return Err("stake can't be zero".to_string());
+        }
+
+        let chips = self.call.chips();
+        let total_chips: u32 = chips.iter().map(|(_, count)| count).sum();
+
+        let chip_value = self.amount.amount.u128() / total_chips as u128;
+
+        if chip_value * total_chips as u128 != self.amount.amount.u128() {
+            return Err(format!(
+                "a stake of {} can't be split evenly over {} chips", self.amount, total_chips
+            ));
+        }
+
+        Ok(chips.into_iter().map(|(result, count)| Bet {
+            amount: Coin::new(chip_value * count as u128, self.amount.denom.clone()),
+            result,
+        }).collect())
+    }
+}
@@ -26,7 +26,8 @@ const initializeClient = async (endpoint: string, chainId: string) => {
 // Stores and instantiates a new contract in our network
 const initializeContract = async (
   client: SecretNetworkClient,
-  contractPath: string
+  contractPath: string,
+  initMsg: object
 ) => {
   const wasmCode = fs.readFileSync(contractPath);
   console.log("Uploading contract");
@@ -71,12 +72,7 @@ const initializeContract = async (
     {
       sender: client.address,
       code_id: codeId,
-      init_msg: {
-        min_bet: 5,
-        max_bet: 1000,
-        max_total: 1_000_000,
-        supported_denoms: ["uscrt"]
-      },
+      init_msg: initMsg,
       code_hash: contractCodeHash,
       label: "My contract" + Math.ceil(Math.random() * 10000), // The label should be unique for every contract, add random string in order to maintain uniqueness
     },
@@ -149,7 +145,25 @@ async function initializeAndUploadContract() {
 
   const [contractHash, contractAddress] = await initializeContract(
     client,
-    "contract.wasm"
+    "contract.wasm",
+    {
+      min_bet: "5",
+      max_bet: "1000",
+      max_total: "1000000",
+      supported_denoms: ["uscrt"]
+    }
+  );
+
+  // the contract refuses bets it can't cover, so give it a bankroll to play with
+  await client.tx.bank.send(
+    {
+      from_address: client.address,
+      to_address: contractAddress,
+      amount: [{denom: "uscrt", amount: "50000000"}],
+    },
+    {
+      gasLimit: 20000,
+    }
   );
 
   const clientInfo: [SecretNetworkClient, SecretNetworkClient, string, string] = [
@@ -207,6 +221,75 @@ async function test_run_game(
   );
 }
 
+// The table asks the chain whether a sender is a contract, and refuses the bet if it can't tell.
+// Accounts have to come back as "no such contract" for them to keep betting once contracts are restricted
+async function test_contract_callers(
+  client: SecretNetworkClient,
+  client2: SecretNetworkClient,
+  contractHash: string,
+  contractAddress: string
+) {
+  const [proxyHash, proxyAddress] = await initializeContract(client, "proxy.wasm", {});
+
+  const bet = {bet: {bets: [{result: "red", amount: {denom: "uscrt", amount: "1000"}}]}};
+
+  const betFromAccount = () => client2.tx.compute.executeContract(
+    {
+      sender: client2.address,
+      contract_address: contractAddress,
+      code_hash: contractHash,
+      msg: bet,
+      sent_funds: [{denom: "uscrt", amount: "1000"}],
+    },
+    {
+      gasLimit: 200000,
+    }
+  );
+
+  const betFromContract = () => client2.tx.compute.executeContract(
+    {
+      sender: client2.address,
+      contract_address: proxyAddress,
+      code_hash: proxyHash,
+      msg: {forward: {contract: contractAddress, code_hash: contractHash, msg: Buffer.from(JSON.stringify(bet)).toString("base64")}},
+      sent_funds: [{denom: "uscrt", amount: "1000"}],
+    },
+    {
+      gasLimit: 300000,
+    }
+  );
+
+  const allowContracts = (contracts: string[]) => client.tx.compute.executeContract(
+    {
+      sender: client.address,
+      contract_address: contractAddress,
+      code_hash: contractHash,
+      msg: {update_config: {contract_callers: {allow_listed: {contracts}}}},
+    },
+    {
+      gasLimit: 100000,
+    }
+  );
+
+  let tx = await betFromContract();
+  assert(tx.code === 0, `contract couldn't bet on an open table: ${tx.rawLog}`);
+
+  tx = await allowContracts([]);
+  assert(tx.code === 0, `couldn't restrict contract callers: ${tx.rawLog}`);
+
+  tx = await betFromAccount();
+  assert(tx.code === 0, `account couldn't bet once contracts were restricted: ${tx.rawLog}`);
+
+  tx = await betFromContract();
+  assert(tx.code !== 0 && tx.rawLog.includes("Unauthorized"), `contract bet on a restricted table: ${tx.rawLog}`);
+
+  tx = await allowContracts([proxyAddress]);
+  assert(tx.code === 0, `couldn't allow list the proxy: ${tx.rawLog}`);
+
+  tx = await betFromContract();
+  assert(tx.code === 0, `allow listed contract couldn't bet: ${tx.rawLog}`);
+}
+
 async function test_gas_limits() {
   // There is no accurate way to measue gas limits but it is actually very recommended to make sure that the gas that
   // is used by a specific tx makes sense
@@ -234,4 +317,8 @@ async function runTestFunction<R>(tester: CallableFunction): Promise<R> {
       test_run_game.bind(this, client, contractHash, contractAddress),
   );
 
+  await runTestFunction<string>(
+      test_contract_callers.bind(this, client, client2, contractHash, contractAddress),
+  );
+
 })();
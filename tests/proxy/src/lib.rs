@@ -0,0 +1,30 @@
+use cosmwasm_std::{entry_point, Binary, DepsMut, Empty, Env, MessageInfo, Response, StdResult, WasmMsg};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Executes `msg` on `contract` as this contract, passing on the funds sent with it
+    Forward {
+        contract: String,
+        code_hash: String,
+        msg: Binary,
+    },
+}
+
+#[entry_point]
+pub fn instantiate(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    Ok(Response::default())
+}
+
+#[entry_point]
+pub fn execute(_deps: DepsMut, _env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+    match msg {
+        ExecuteMsg::Forward { contract, code_hash, msg } => Ok(Response::new().add_message(WasmMsg::Execute {
+            contract_addr: contract,
+            code_hash,
+            msg,
+            funds: info.funds,
+        })),
+    }
+}